@@ -0,0 +1,295 @@
+use crate::client;
+use crate::store::write_atomically;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use strum::{Display, EnumString, EnumVariantNames};
+use url::Url;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Display, EnumString, EnumVariantNames, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ArtKind {
+    Poster,
+    Fanart,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Display, EnumString, EnumVariantNames, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ArtSize {
+    Small,
+    Medium,
+    Large,
+}
+
+/// Where downloaded artwork is kept.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArtSettings {
+    pub dir: Option<PathBuf>,
+}
+
+/// Content addressed artwork: every image is stored once, named after the sha1 of its bytes,
+/// with an index that remembers which url had which content.
+#[derive(Debug)]
+pub struct ArtCache {
+    dir: PathBuf,
+}
+
+const INDEX: &str = "index.json";
+
+/// Held while the index gets read, changed and written back, as the http server fetches
+/// artwork from several threads at once.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+impl ArtCache {
+    pub fn new(dir: PathBuf) -> ArtCache {
+        ArtCache { dir }
+    }
+
+    /// The configured directory, or else `yts/art` in the user's cache directory.
+    pub fn from_settings(settings: &ArtSettings) -> Result<ArtCache, Box<dyn Error>> {
+        let dir = match &settings.dir {
+            Some(dir) => dir.clone(),
+            None => dirs::cache_dir()
+                .ok_or("can't find the user's cache directory, please configure [art] dir")?
+                .join("yts")
+                .join("art"),
+        };
+        Ok(ArtCache::new(dir))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn index(&self) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+        let path = self.dir.join(INDEX);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// The cached file for the url, if it was fetched before.
+    pub fn lookup(&self, url: &Url) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(self
+            .index()?
+            .get(url.as_str())
+            .map(|name| self.dir.join(name))
+            .filter(|path| path.exists()))
+    }
+
+    /// The cached file for the url, downloading it first if needed.
+    pub fn fetch(&self, url: &Url) -> Result<PathBuf, Box<dyn Error>> {
+        if let Some(path) = self.lookup(url)? {
+            return Ok(path);
+        }
+
+        let bytes = client::shared()?
+            .get(url, HeaderMap::new())?
+            .error_for_status()?
+            .bytes()?;
+        let extension =
+            image_extension(&bytes).ok_or_else(|| format!("{} is not an image", url))?;
+        let name = format!("{}.{}", hex(&Sha1::digest(&bytes)), extension);
+
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(&name);
+        if !path.exists() {
+            write_atomically(&path, &bytes)?;
+        }
+        self.remember(url, name)?;
+
+        Ok(path)
+    }
+
+    /// Adds the url and the name of its file to the index.
+    fn remember(&self, url: &Url, name: String) -> Result<(), Box<dyn Error>> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.index()?;
+        index.insert(url.to_string(), name);
+        write_atomically(
+            &self.dir.join(INDEX),
+            serde_json::to_string_pretty(&index)?.as_bytes(),
+        )
+    }
+
+    /// The cached file with the given name. Anything that isn't the name of a cached image is None,
+    /// so that names coming from the outside can't reach other files.
+    pub fn get(&self, name: &str) -> Option<PathBuf> {
+        let (hash, extension) = name.split_once('.')?;
+        let valid = hash.len() == 40
+            && hash
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase())
+            && ["jpg", "png", "webp", "gif"].contains(&extension);
+        let path = self.dir.join(name);
+        (valid && path.is_file()).then_some(path)
+    }
+}
+
+/// The mime type for a cached image.
+pub fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        _ => "image/jpeg",
+    }
+}
+
+fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"\x89PNG") {
+        Some("png")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some("webp")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("gif")
+    } else {
+        None
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::artwork::{content_type, ArtCache, ArtKind, ArtSize};
+    use crate::parse::api::{ListResponse, Movie};
+    use std::path::PathBuf;
+    use url::Url;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movie() -> Movie {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap().remove(0)
+    }
+
+    fn cache(name: &str) -> ArtCache {
+        ArtCache::new(std::env::temp_dir().join(format!("yts-art-{}-{}", name, std::process::id())))
+    }
+
+    #[test]
+    fn picks_sizes() {
+        let mut movie = movie();
+        let path =
+            |url: Option<&Url>| url.map(|u| u.path().rsplit('/').next().unwrap().to_string());
+        assert_eq!(
+            path(movie.artwork(ArtKind::Poster, ArtSize::Small)).as_deref(),
+            Some("small-cover.jpg")
+        );
+        assert_eq!(
+            path(movie.artwork(ArtKind::Fanart, ArtSize::Large)).as_deref(),
+            Some("background.jpg")
+        );
+
+        movie.large_cover_image = None;
+        assert_eq!(
+            path(movie.artwork(ArtKind::Poster, ArtSize::Large)).as_deref(),
+            Some("medium-cover.jpg")
+        );
+    }
+
+    #[test]
+    fn empty_urls_are_missing() {
+        let json = JSON.replacen(
+            "\"small_cover_image\": \"https:\\/\\/yts.mx\\/assets\\/images\\/movies\\/la_via_dei_babbuini_1974\\/small-cover.jpg\"",
+            "\"small_cover_image\": \"\"",
+            1,
+        );
+        assert_ne!(json, JSON);
+        let response: ListResponse = serde_json::from_str(&json).unwrap();
+        assert!(response.data.unwrap().movies.unwrap()[0]
+            .small_cover_image
+            .is_none());
+    }
+
+    #[test]
+    fn serves_only_cached_images() {
+        let cache = cache("get");
+        std::fs::create_dir_all(cache.dir()).unwrap();
+        let name = format!("{}.jpg", "AB".repeat(20));
+        std::fs::write(cache.dir().join(&name), [0xFF, 0xD8, 0xFF]).unwrap();
+        std::fs::write(cache.dir().join("index.json"), "{}").unwrap();
+
+        let found = cache.get(&name);
+        assert_eq!(found.as_deref().map(content_type), Some("image/jpeg"));
+        assert!(cache.get("index.json").is_none());
+        assert!(cache.get("../etc/passwd.jpg").is_none());
+        assert!(cache.get(&name.to_lowercase()).is_none());
+        assert!(cache.get(&format!("{}.jpg", "CD".repeat(20))).is_none());
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn fetches_once() {
+        let mut server = mockito::Server::new();
+        let image = server
+            .mock("GET", "/poster.png")
+            .with_body(b"\x89PNG fake")
+            .expect(1)
+            .create();
+        let cache = cache("fetch");
+        let url = Url::parse(&format!("{}/poster.png", server.url())).unwrap();
+
+        let path: PathBuf = cache.fetch(&url).unwrap();
+        assert!(path.to_str().unwrap().ends_with(".png"));
+        assert_eq!(cache.fetch(&url).unwrap(), path);
+        assert_eq!(cache.lookup(&url).unwrap(), Some(path));
+        image.assert();
+
+        server.mock("GET", "/page").with_body("<html>").create();
+        let page = Url::parse(&format!("{}/page", server.url())).unwrap();
+        assert!(cache.fetch(&page).is_err());
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn fetches_concurrently() {
+        let mut server = mockito::Server::new();
+        let cache = cache("concurrent");
+        let urls: Vec<Url> = (0..8u8)
+            .map(|i| {
+                let path = format!("/poster-{}.png", i);
+                server
+                    .mock("GET", path.as_str())
+                    .with_body([&b"\x89PNG "[..], &[i]].concat())
+                    .create();
+                Url::parse(&format!("{}{}", server.url(), path)).unwrap()
+            })
+            .collect();
+
+        std::thread::scope(|scope| {
+            for url in &urls {
+                let cache = &cache;
+                scope.spawn(move || cache.fetch(url).unwrap());
+            }
+        });
+        for url in &urls {
+            assert!(cache.lookup(url).unwrap().is_some(), "{} got lost", url);
+        }
+        let leftovers = std::fs::read_dir(cache.dir())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .path()
+                    .to_string_lossy()
+                    .ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// A decoded bencode value, as found in .torrent files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+/// Why some input isn't valid bencode, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid bencode at byte {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Deepest nesting of lists and dictionaries accepted, so that hostile input can't blow the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Decodes a single value that has to span the whole input.
+pub fn decode(input: &[u8]) -> Result<Value, DecodeError> {
+    let mut decoder = Decoder { input, position: 0 };
+    let value = decoder.value(0)?;
+    decoder.end()?;
+    Ok(value)
+}
+
+/// The entries of the top level dictionary, as raw bencode.
+/// The info-hash of a torrent is the sha1 of the raw `info` entry, which re-encoding could alter.
+pub fn raw_entries(input: &[u8]) -> Result<BTreeMap<Vec<u8>, &[u8]>, DecodeError> {
+    let mut decoder = Decoder { input, position: 0 };
+    let mut entries = BTreeMap::new();
+
+    decoder.expect(b'd')?;
+    while decoder.peek()? != b'e' {
+        let key = decoder.bytes()?;
+        let start = decoder.position;
+        decoder.value(1)?;
+        entries.insert(key, &input[start..decoder.position]);
+    }
+    decoder.position += 1;
+    decoder.end()?;
+
+    Ok(entries)
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(dict) => dict.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The value as text, if it's a valid utf-8 byte string.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, message: impl Into<String>) -> DecodeError {
+        DecodeError {
+            position: self.position,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Result<u8, DecodeError> {
+        self.input
+            .get(self.position)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end of input"))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), DecodeError> {
+        if self.peek()? != byte {
+            return Err(self.error(format!("expected '{}'", byte as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn end(&self) -> Result<(), DecodeError> {
+        if self.position != self.input.len() {
+            return Err(self.error("trailing data"));
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        match self.peek()? {
+            b'i' => self.int().map(Value::Int),
+            b'0'..=b'9' => self.bytes().map(Value::Bytes),
+            b'l' => {
+                self.position += 1;
+                let mut list = vec![];
+                while self.peek()? != b'e' {
+                    list.push(self.value(depth + 1)?);
+                }
+                self.position += 1;
+                Ok(Value::List(list))
+            }
+            b'd' => {
+                self.position += 1;
+                let mut dict = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    let value = self.value(depth + 1)?;
+                    dict.insert(key, value);
+                }
+                self.position += 1;
+                Ok(Value::Dict(dict))
+            }
+            byte => Err(self.error(format!("unexpected '{}'", byte as char))),
+        }
+    }
+
+    /// The digits up to `terminator`, which gets consumed.
+    fn digits(&mut self, terminator: u8) -> Result<&'a str, DecodeError> {
+        let start = self.position;
+        while self.peek()? != terminator {
+            self.position += 1;
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.position])
+            .map_err(|_| self.error("invalid number"))?;
+        self.position += 1;
+        Ok(digits)
+    }
+
+    fn int(&mut self) -> Result<i64, DecodeError> {
+        self.expect(b'i')?;
+        let start = self.position;
+        let digits = self.digits(b'e')?;
+        let canonical = digits == "0"
+            || (!digits.starts_with('0') && !digits.starts_with("-0") && !digits.is_empty());
+        match digits.parse() {
+            Ok(i) if canonical => Ok(i),
+            _ => Err(DecodeError {
+                position: start,
+                message: format!("invalid integer '{}'", digits),
+            }),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let start = self.position;
+        let length: usize = self.digits(b':')?.parse().map_err(|_| DecodeError {
+            position: start,
+            message: "invalid string length".to_string(),
+        })?;
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.input.len())
+            .ok_or_else(|| self.error("string runs past the end of input"))?;
+        let bytes = self.input[self.position..end].to_vec();
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencode::{decode, raw_entries, Value};
+
+    #[test]
+    fn decodes() {
+        let value = decode(b"d3:cow3:moo4:spaml1:a1:bi-3eee").unwrap();
+        assert_eq!(value.get("cow").and_then(Value::as_str), Some("moo"));
+        assert_eq!(
+            value.get("spam").and_then(Value::as_list).map(|l| l.len()),
+            Some(3)
+        );
+        assert_eq!(
+            value.get("spam").unwrap().as_list().unwrap()[2],
+            Value::Int(-3)
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        for input in [
+            &b""[..],
+            b"i03e",
+            b"i-0e",
+            b"ie",
+            b"5:abc",
+            b"l1:a",
+            b"d1:ae",
+            b"i1ei2e",
+            b"<html>",
+        ] {
+            assert!(
+                decode(input).is_err(),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+        assert!(decode(&b"l".repeat(1000)).is_err());
+    }
+
+    #[test]
+    fn raw() {
+        let entries = raw_entries(b"d4:infod6:lengthi5ee4:name1:xe").unwrap();
+        assert_eq!(entries[&b"info"[..]], b"d6:lengthi5ee");
+        assert_eq!(entries[&b"name"[..]], b"1:x");
+    }
+}
@@ -1,60 +1,708 @@
-use clap::{App, AppSettings, Arg};
+use crate::cli::yts::output::Format;
+use clap::builder::{PossibleValue, TypedValueParser};
+use clap::error::{Error, ErrorKind};
+use clap::{App, AppSettings, Arg, Command};
+use clap_complete::Shell;
+use std::ffi::OsStr;
+use strum::VariantNames;
+use yts::artwork::{ArtKind, ArtSize};
+use yts::date::Since;
+use yts::import::Service;
+use yts::request::Genre;
+use yts::size::ByteSize;
+
+const MIRRORS: [&str; 4] = ["yts.mx", "yts.lt", "yts.am", "yts.ag"];
 
 pub fn clap_app() -> App<'static> {
     clap::command!()
         .global_setting(AppSettings::NextLineHelp)
         .global_setting(AppSettings::ColoredHelp)
+        .arg(
+            Arg::with_name("config").long("config").takes_value(true).global(true)
+                .help("path to the config file")
+                .long_help("Path to the config file. Defaults to yts/config.toml in the user's config directory"),
+        )
+        .arg(
+            Arg::with_name("data-dir").long("data-dir").takes_value(true).global(true)
+                .help("where to keep the catalogue, lists and other state")
+                .long_help("Where to keep the catalogue, lists and other state. Defaults to yts in the user's data directory"),
+        )
+        .arg(
+            Arg::with_name("strict").long("strict").global(true)
+                .help("fail on api responses that don't fit the expected schema")
+                .long_help("Fail on api responses with unknown fields, fields of the wrong type or missing required fields,\n\
+                listing each of them by its json path. Can be turned on for good with strict = true in the config file"),
+        )
         .subcommand(
             App::new("list")
                 .about("lists movies")
-                .args(&[
-                    Arg::with_name("search").takes_value(true).help("Search query")
-                        .multiple(true)
-                        .long_help("Search query, matching on: Movie Title/IMDb Code, Actor Name/IMDb Code, Director Name/IMDb Code"),
-                    Arg::with_name("limit").short('l').long("limit").takes_value(true)
-                        .default_value("50")
-                        .help("The limit of results per page that has been set")
-                        .long_help("The limit of results per page that has been set.\n\
-                        Integer between 1 - 50 (inclusive)"),
-                    Arg::with_name("page")
-                        .takes_value(true).short('p').long("page")
-                        .help("The page in the list of movies")
-                        .long_help("The page in the list of movies\n\
-                        e.g. limit=15 and page=2 will show you movies 15-30\n\
-                        Integer (Unsigned)")
-                        .value_parser(clap::value_parser!(u16).range(1..)),
-                    Arg::with_name("quality")
-                        .long("quality").short('q').takes_value(true)
-                        .case_insensitive(true).possible_values(&["720p", "1080p", "2160p", "3D"])
-                        .help("Filter by a given quality")
-                        .long_help("Filter by a given quality\nString (720p, 1080p, 2160p, 3D)"),
-                    Arg::with_name("rating")
-                        .takes_value(true).short('r').long("rating")
-                        .help("Filter movie by a given minimum IMDb rating")
-                        .long_help("Filter movie by a given minimum IMDb rating\nInteger between 0 - 9 (inclusive)")
-                        .value_parser(clap::value_parser!(u16).range(1..9)),
-                    Arg::with_name("genre").takes_value(true).short('g').long("genre")
-                        .help("Filter by a given genre")
-                        .long_help("Filter by a given genre (See http://www.imdb.com/genre/ for full list)"),
-                    Arg::with_name("sort").long("sort").short('s').takes_value(true)
-                        .case_insensitive(true)
-                        .possible_values(&["title", "year", "rating", "peers", "seeds", "download_count", "like_count", "date_added"])
-                        .help("Sorts the results by a criteria")
-                        .long_help("Sorts the results by a criteria\nString (title, year, rating, peers, seeds, download_count, like_count, date_added)"),
-                    Arg::with_name("order").long("order").short('o').takes_value(true)
-                        .case_insensitive(true)
-                        .possible_values(&["desc", "asc"])
-                        .help("Order the results ascending or descending")
-                        .long_help("Order the results ascending or descending. String. 'asc' or 'desc'."),
-                    Arg::with_name("with_rotten_tomatoes").long("rt")
-                        .help("get rotten tomatoes ratings")
-                        .long_help("Return the list with the Rotten Tomatoes rating included"),
-                    Arg::with_name("mirror").takes_value(true).long("mirror").help("domain / mirror to use")
-                        .long_help("will switch the domain to a mirror")
-                        .possible_values(&["yts.mx", "yts.lt", "yts.am", "yts.ag"]).default_value("yts.mx"),
+                .args(list_args())
+                .args(shown_args())
+                .arg(format_arg())
+                .arg(
                     Arg::with_name("response-mock-file").long("response-mock").short('m')
                         .help("does not perform any connection, but uses a local json instead")
                         .takes_value(true).hidden(true),
+                ),
+        )
+        .subcommand(
+            App::new("tui")
+                .about("browses movies interactively")
+                .long_about("A full-screen movie browser for the same query as the list command, with a detail pane\n\
+                and keys to copy magnets, open trailers, add to the watchlist and download .torrent files.\n\
+                More pages are loaded when scrolling past the end.")
+                .args(list_args())
+                .arg(
+                    Arg::with_name("response-mock-file").long("response-mock").short('m')
+                        .help("does not perform any connection, but uses a local json instead")
+                        .takes_value(true).hidden(true),
+                ),
+        )
+        .subcommand(
+            App::new("sync")
+                .about("syncs the yts catalogue into a local database")
+                .long_about("Walks the movie list from the newest additions backwards and stores it in a local database.\n\
+                A walk that stops halfway is picked up where it stopped by the next run. Once the whole catalogue\n\
+                is in, subsequent runs only fetch what was added since the newest known movie.")
+                .args(&[
+                    db_arg(),
+                    Arg::with_name("full").long("full")
+                        .help("walk the whole catalogue")
+                        .long_help("Ignore what is already in the database and walk the whole catalogue"),
+                    mirror_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("watch")
+                .about("watches for new movies")
+                .long_about("Runs the same query as the list command every --interval seconds and reports\n\
+                the movies that weren't seen before, as well as new torrents (e.g. a 2160p one)\n\
+                for movies that were already seen.")
+                .args(list_args())
+                .args(&[
+                    Arg::with_name("interval").long("interval").short('i').takes_value(true)
+                        .default_value("900")
+                        .help("seconds between checks")
+                        .value_parser(clap::value_parser!(u64).range(1..)),
+                    Arg::with_name("once").long("once")
+                        .help("check once and exit"),
+                    Arg::with_name("prime").long("prime")
+                        .help("record what is there without reporting it")
+                        .long_help("Record everything the query currently returns as seen without reporting it,\n\
+                        so that the next checks only report what gets added afterwards"),
+                    Arg::with_name("state").long("state").takes_value(true)
+                        .help("where to keep what was already seen")
+                        .long_help("Where to keep what was already seen. Use different files for different queries.\n\
+                        Defaults to watch.json in the user's data directory"),
+                    Arg::with_name("notify-cmd").long("notify-cmd").takes_value(true)
+                        .help("shell command to run for every new item")
+                        .long_help("Shell command to run for every new item, e.g. 'notify-send \"$YTS_MESSAGE\"'.\n\
+                        The item is described by the YTS_EVENT, YTS_MESSAGE, YTS_ID, YTS_TITLE, YTS_YEAR,\n\
+                        YTS_QUALITY and YTS_URL environment variables"),
+                    Arg::with_name("webhook").long("webhook").takes_value(true)
+                        .help("url to POST every new item to, as json")
+                        .value_parser(clap::value_parser!(url::Url)),
+                ]),
+        )
+        .subcommand(
+            App::new("watchlist")
+                .about("keeps track of the movies you want to watch")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("add").about("adds movies to the watchlist")
+                        .args(&[movies_arg(), mirror_arg()]),
+                )
+                .subcommand(
+                    App::new("rm").about("removes movies from the watchlist")
+                        .arg(movies_arg()),
+                )
+                .subcommand(
+                    App::new("ls").about("lists the watchlist")
+                        .arg(format_arg()),
+                )
+                .subcommand(
+                    App::new("check")
+                        .about("reports which watchlist titles have a torrent in the preferred quality")
+                        .long_about("Looks the watchlist titles up again and reports which of them have a torrent\n\
+                        in the preferred quality, which is --quality, or else the one from the config file, or else 1080p")
+                        .args(&[
+                            Arg::with_name("quality")
+                                .long("quality").short('q').takes_value(true)
+                                .case_insensitive(true).possible_values(["720p", "1080p", "2160p", "3D"])
+                                .help("the preferred quality"),
+                            mirror_arg(),
+                        ]),
+                ),
+        )
+        .subcommand(
+            App::new("seen")
+                .about("keeps track of the movies you've already watched")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("add").about("marks movies as seen, which also takes them off the watchlist")
+                        .args(&[movies_arg(), mirror_arg()]),
+                )
+                .subcommand(
+                    App::new("rm").about("unmarks movies as seen")
+                        .arg(movies_arg()),
+                )
+                .subcommand(
+                    App::new("ls").about("lists the movies marked as seen")
+                        .arg(format_arg()),
+                ),
+        )
+        .subcommand(
+            App::new("get")
+                .about("downloads .torrent files")
+                .long_about("Downloads the .torrent file that matches --quality and --type best, preferring\n\
+                higher qualities and bluray rips when they're not given. Ties go to the most seeded torrent.")
+                .args(&[
+                    movies_arg(),
+                    Arg::with_name("dir")
+                        .long("dir").short('o').takes_value(true)
+                        .help("where to save the files")
+                        .long_help("Where to save the files. Defaults to the one in the config file, or else to the current directory"),
+                    Arg::with_name("name")
+                        .long("name").takes_value(true)
+                        .help("file name template")
+                        .long_help("File name template, with {title}, {year}, {quality}, {type}, {id}, {imdb} and {hash}\n\
+                        placeholders. Defaults to the one in the config file, or else to \"{title} ({year}) [{quality}].torrent\""),
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("only print what would be downloaded"),
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("replace files that are already there"),
+                    mirror_arg(),
+                ])
+                .args(torrent_args()),
+        )
+        .subcommand(
+            App::new("send")
+                .about("sends torrents to a torrent client")
+                .long_about("Sends the magnet link of the torrent that matches --quality and --type best (see get)\n\
+                to one of the clients from the config file. With --file the .torrent file gets sent instead.")
+                .args(&[
+                    movies_arg(),
+                    Arg::with_name("to")
+                        .long("to").takes_value(true)
+                        .help("the client to send to")
+                        .long_help("The name of the client to send to, from the config file. Can be left out when there's only one"),
+                    Arg::with_name("file")
+                        .long("file")
+                        .help("send the .torrent file instead of the magnet link"),
+                    Arg::with_name("category")
+                        .long("category").takes_value(true)
+                        .help("category (label for transmission), defaults to the client's one from the config file"),
+                    Arg::with_name("save-path")
+                        .long("save-path").takes_value(true)
+                        .help("download directory, defaults to the client's one from the config file"),
+                    Arg::with_name("paused")
+                        .long("paused")
+                        .help("add the torrents paused"),
+                    mirror_arg(),
                 ])
+                .args(torrent_args()),
+        )
+        .subcommand(
+            App::new("inspect")
+                .about("shows what's in a .torrent file")
+                .long_about("Shows what's in a .torrent file and checks its info-hash and size against what yts says\n\
+                about the torrent, which is looked up among the torrents of --movie, or else in the local catalogue")
+                .args(&[
+                    Arg::with_name("file").takes_value(true).required(true)
+                        .help("the .torrent file"),
+                    Arg::with_name("movie").long("movie").takes_value(true)
+                        .help("the movie the torrent should belong to, as a yts id or imdb code"),
+                    db_arg(),
+                    mirror_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("library")
+                .about("keeps track of the movies you already have")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("scan")
+                        .about("finds the catalogue movies in a directory")
+                        .long_about("Walks the directory for video files and matches them against the local catalogue,\n\
+                        by the imdb codes in .nfo files or else by the title and year in their names.\n\
+                        Reports the watchlist titles that are missing, the owned movies that have a torrent in a\n\
+                        better quality and the files that couldn't be matched. The result is used by list --hide-owned.")
+                        .args(&[
+                            Arg::with_name("dir").takes_value(true).required(true)
+                                .help("the directory with the movies"),
+                            db_arg(),
+                        ]),
+                ),
+        )
+        .subcommand(
+            App::new("nfo")
+                .about("writes a Kodi style movie.nfo")
+                .long_about("Writes a Kodi style movie.nfo with the movie's metadata, which Jellyfin and Emby read as well")
+                .args(&[
+                    Arg::with_name("movie").takes_value(true).required(true)
+                        .help("yts id or imdb code"),
+                    Arg::with_name("out").long("out").takes_value(true)
+                        .help("the directory to write movie.nfo to, defaults to the current one"),
+                    mirror_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("art")
+                .about("downloads artwork")
+                .long_about("Downloads a movie's poster or fanart into the local artwork cache and prints where it is.\n\
+                The cache is yts/art in the user's cache directory unless the config file says otherwise")
+                .args(&[
+                    Arg::with_name("movie").takes_value(true).required(true)
+                        .help("yts id or imdb code"),
+                    Arg::with_name("kind").long("kind").short('k').takes_value(true)
+                        .possible_values(ArtKind::VARIANTS).default_value("poster")
+                        .help("which image"),
+                    Arg::with_name("size").long("size").short('s').takes_value(true)
+                        .possible_values(ArtSize::VARIANTS).default_value("large")
+                        .help("which size, or the closest there is"),
+                    mirror_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("search")
+                .about("searches movies")
+                .long_about("Searches movies through the api or, with --offline, through the local catalogue.\n\
+                Offline queries are ranked by relevance over titles, summaries, descriptions and genres\n\
+                and support \"quoted phrases\", prefix* matching and typos in titles.")
+                .args(&[
+                    Arg::with_name("query").takes_value(true).multiple(true).required(true)
+                        .help("what to search for"),
+                    Arg::with_name("offline").long("offline")
+                        .help("search the local catalogue")
+                        .long_help("Search the local catalogue created by the sync command instead of the api"),
+                    Arg::with_name("limit").short('l').long("limit").takes_value(true)
+                        .default_value("20")
+                        .help("The maximum number of results")
+                        .value_parser(clap::value_parser!(u8).range(1..=50)),
+                    db_arg(),
+                    mirror_arg(),
+                    format_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("genres")
+                .about("lists the genres")
+                .long_about("Lists the genres that can be filtered by, with the number of movies in each\n\
+                when there's a local catalogue")
+                .arg(db_arg()),
+        )
+        .subcommand(
+            App::new("completions")
+                .about("prints a shell completion script")
+                .long_about("Prints the completion script for the given shell, e.g.\n\
+                yts completions bash > /etc/bash_completion.d/yts")
+                .arg(
+                    Arg::with_name("shell").takes_value(true).required(true)
+                        .help("the shell to complete for")
+                        .value_parser(clap::value_parser!(Shell)),
+                ),
+        )
+        .subcommand(
+            App::new("man")
+                .about("prints the man page")
+                .long_about("Prints the man page in roff, e.g. yts man > /usr/local/share/man/man1/yts.1"),
+        )
+        .subcommand(
+            App::new("snapshot")
+                .about("saves movies to compare against later")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("save")
+                        .about("saves a snapshot of a list or of the local catalogue")
+                        .long_about("Saves the movies the list command would show, or with --offline the ones from the local\n\
+                        catalogue, to a file that yts diff can compare against a later one")
+                        .arg(
+                            Arg::with_name("file").takes_value(true).required(true)
+                                .help("where to save the snapshot"),
+                        )
+                        .args(list_args())
+                        .args(&[
+                            Arg::with_name("offline").long("offline")
+                                .help("snapshot the local catalogue")
+                                .long_help("Take the movies from the local catalogue created by the sync command instead of the api"),
+                            Arg::with_name("all").long("all")
+                                .help("snapshot the whole local catalogue")
+                                .long_help("Take every movie in the local catalogue, regardless of the filters, the limit and the page"),
+                            db_arg(),
+                        ]),
+                ),
         )
+        .subcommand(
+            App::new("diff")
+                .about("compares two snapshots")
+                .long_about("Reports the new and removed movies, the new and removed torrents and the changed ratings\n\
+                between two snapshots taken by yts snapshot save. Removed movies are only reported when both\n\
+                snapshots are of the whole catalogue (--all), as movies leave a list page without being removed")
+                .args(&[
+                    Arg::with_name("old").takes_value(true).required(true)
+                        .help("the older snapshot"),
+                    Arg::with_name("new").takes_value(true).required(true)
+                        .help("the newer snapshot"),
+                    format_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("doctor")
+                .about("checks that things work as expected")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("schema")
+                        .about("reports where the api responses drifted from the expected schema")
+                        .long_about("Fetches a page of movies and the details of the first one and reports every unknown field,\n\
+                        type mismatch and missing required field, grouped by json path.\n\
+                        Exits with 1 when there's any drift")
+                        .args(&[
+                            Arg::with_name("limit").short('l').long("limit").takes_value(true)
+                                .default_value("20")
+                                .help("how many movies to check")
+                                .value_parser(clap::value_parser!(u8).range(1..=50)),
+                            Arg::with_name("file").long("file").takes_value(true)
+                                .help("check a saved list or details response instead"),
+                            mirror_arg(),
+                        ]),
+                ),
+        )
+        .subcommand(
+            App::new("stats")
+                .about("shows what the catalogue is made of")
+                .long_about("Counts the movies per genre, decade (or --year-span years) and rating, how many come in each\n\
+                quality and how big those torrents are on average, and how well the torrents are seeded.\n\
+                Takes the same filters as the list command and runs over every matching movie of the local\n\
+                catalogue, or with --online over --pages pages of the api, starting at --page")
+                .args(list_args())
+                .args(&[
+                    Arg::with_name("online").long("online")
+                        .help("use the api instead of the local catalogue"),
+                    Arg::with_name("pages").long("pages").takes_value(true).requires("online")
+                        .help("how many pages of the api to go through, 1 by default")
+                        .value_parser(clap::value_parser!(u32).range(1..)),
+                    Arg::with_name("year-span").long("year-span").takes_value(true).default_value("10")
+                        .help("how many years to count the movies together by, e.g. 1 for every year")
+                        .value_parser(clap::value_parser!(u16).range(1..)),
+                    db_arg(),
+                    format_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("import")
+                .about("adds the movies of a Letterboxd, Trakt or IMDb export to the watchlist")
+                .long_about("Reads a csv list or watchlist export of Letterboxd, Trakt or IMDb and adds the movies yts has\n\
+                to the watchlist. Entries are found by their imdb code, or else by title and year, allowing for\n\
+                small differences in both. Shows and episodes are skipped. Prints a report of the matched,\n\
+                ambiguous and missing titles; ambiguous ones are left for watchlist add")
+                .args(&[
+                    Arg::with_name("file").takes_value(true).required(true)
+                        .help("the csv export"),
+                    Arg::with_name("from").long("from").takes_value(true).required(true)
+                        .possible_values(Service::VARIANTS)
+                        .help("the site the export comes from"),
+                    Arg::with_name("offline").long("offline")
+                        .help("look the titles up in the local catalogue instead of the api"),
+                    Arg::with_name("dry-run").long("dry-run")
+                        .help("only print the report, leaving the watchlist as it is"),
+                    Arg::with_name("jobs").long("jobs").short('j').takes_value(true)
+                        .help("how many titles to look up at once").default_value("4")
+                        .value_parser(clap::value_parser!(u8).range(1..=16)),
+                    db_arg(),
+                    mirror_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("enrich")
+                .about("adds IMDb votes, directors, writers and original titles from the IMDb datasets")
+                .long_about("Reads the title.basics, title.ratings and title.crew dumps from https://datasets.imdbws.com\n\
+                (and name.basics, for the names of the crew, if it's there) and keeps what they say about the movies\n\
+                of the local catalogue. From then on lists show it with --imdb and can be filtered with --min-votes,\n\
+                --director and --original-title. Nothing is downloaded, the files have to be there already")
+                .args(&[
+                    Arg::with_name("imdb-datasets").long("imdb-datasets").takes_value(true).required(true)
+                        .help("the directory with the .tsv.gz files"),
+                    db_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("lookup")
+                .about("looks up a list of movies")
+                .long_about("Looks up every yts id or imdb code of a file, one per line, and prints the movies yts has.\n\
+                Blank lines, # comments and anything after the first word are skipped, so imdb urls work too.\n\
+                The ids yts doesn't have are reported on stderr, and the exit code is 1 if there are any")
+                .args(&[
+                    Arg::with_name("file").takes_value(true).required(true)
+                        .help("the file with the ids, or - for stdin"),
+                    Arg::with_name("jobs").long("jobs").short('j').takes_value(true)
+                        .help("how many movies to look up at once").default_value("4")
+                        .value_parser(clap::value_parser!(u8).range(1..=16)),
+                    mirror_arg(),
+                    format_arg(),
+                ]),
+        )
+        .subcommand(
+            App::new("db")
+                .about("works with the local catalogue database")
+                .subcommand_required(true)
+                .subcommand(
+                    App::new("query")
+                        .about("lists movies from the local catalogue")
+                        .long_about("Lists movies from the local catalogue, using the same filters as the list command")
+                        .args(list_args())
+                        .args(shown_args())
+                        .args(&[db_arg(), format_arg()]),
+                ),
+        )
+}
+
+/// The filtering arguments of the list command, shared by everything that builds a ListRequest.
+fn list_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::with_name("search").takes_value(true).help("Search query")
+            .multiple(true)
+            .long_help("Search query, matching on: Movie Title/IMDb Code, Actor Name/IMDb Code, Director Name/IMDb Code.\n\
+            A word starting with @ uses the arguments of that profile from the config file, e.g. @4k-scifi"),
+        Arg::with_name("limit").short('l').long("limit").takes_value(true)
+            .default_value("50")
+            .help("The limit of results per page that has been set")
+            .long_help("The limit of results per page that has been set.\n\
+            Integer between 1 - 50 (inclusive)"),
+        Arg::with_name("page")
+            .takes_value(true).short('p').long("page")
+            .help("The page in the list of movies")
+            .long_help("The page in the list of movies\n\
+            e.g. limit=15 and page=2 will show you movies 15-30\n\
+            Integer (Unsigned)")
+            .value_parser(clap::value_parser!(u16).range(1..)),
+        Arg::with_name("quality")
+            .long("quality").short('q').takes_value(true)
+            .case_insensitive(true).possible_values(["720p", "1080p", "2160p", "3D"])
+            .help("Filter by a given quality")
+            .long_help("Filter by a given quality\nString (720p, 1080p, 2160p, 3D)"),
+        Arg::with_name("rating")
+            .takes_value(true).short('r').long("rating")
+            .help("Filter movie by a given minimum IMDb rating")
+            .long_help("Filter movie by a given minimum IMDb rating\nInteger between 0 - 9 (inclusive)")
+            .value_parser(clap::value_parser!(u8).range(1..9)),
+        Arg::with_name("genre").takes_value(true).short('g').long("genre")
+            .value_parser(GenreParser)
+            .help("Filter by a given genre")
+            .long_help("Filter by a given genre (See `yts genres` for the full list)"),
+        Arg::with_name("sort").long("sort").short('s').takes_value(true)
+            .case_insensitive(true)
+            .possible_values(["title", "year", "rating", "peers", "seeds", "download_count", "like_count", "date_added"])
+            .help("Sorts the results by a criteria")
+            .long_help("Sorts the results by a criteria\nString (title, year, rating, peers, seeds, download_count, like_count, date_added)"),
+        Arg::with_name("order").long("order").short('o').takes_value(true)
+            .case_insensitive(true)
+            .possible_values(["desc", "asc"])
+            .help("Order the results ascending or descending")
+            .long_help("Order the results ascending or descending. String. 'asc' or 'desc'."),
+        Arg::with_name("with_rotten_tomatoes").long("rt")
+            .help("get rotten tomatoes ratings")
+            .long_help("Return the list with the Rotten Tomatoes rating included"),
+        mirror_arg(),
+        Arg::with_name("min-rating").long("min-rating").takes_value(true)
+            .help("Filter out movies rated below this")
+            .long_help("Filter out movies rated below this, after they were retrieved. Unlike --rating it can be fractional, e.g. 7.5")
+            .value_parser(clap::value_parser!(f32)),
+        Arg::with_name("min-year").long("min-year").takes_value(true)
+            .help("Filter out movies released before this year")
+            .value_parser(clap::value_parser!(u16)),
+        Arg::with_name("max-year").long("max-year").takes_value(true)
+            .help("Filter out movies released after this year")
+            .value_parser(clap::value_parser!(u16)),
+        Arg::with_name("language").long("language").takes_value(true)
+            .help("Only keep movies in this language")
+            .long_help("Only keep movies in this language, given as a two letter code, e.g. en"),
+        min_size_arg(),
+        max_size_arg(),
+        Arg::with_name("since").long("since").takes_value(true)
+            .help("Only keep movies uploaded since then")
+            .long_help("Only keep movies uploaded since then, given as a date (2024-01-01, 2024-01-01T12:00:00+02:00)\n\
+            or a time ago (12h, 7d, 2w)")
+            .value_parser(clap::value_parser!(Since)),
+        Arg::with_name("min-votes").long("min-votes").takes_value(true)
+            .help("Only keep movies with at least this many IMDb votes, see the enrich command")
+            .value_parser(clap::value_parser!(u32)),
+        Arg::with_name("director").long("director").takes_value(true)
+            .help("Only keep movies by a director whose name contains this, see the enrich command"),
+        Arg::with_name("original-title").long("original-title").takes_value(true)
+            .help("Only keep movies whose original title contains this, see the enrich command"),
+        Arg::with_name("hide-seen").long("hide-seen")
+            .help("Leave out the movies marked as seen"),
+        Arg::with_name("hide-owned").long("hide-owned")
+            .help("Leave out the movies found by the last library scan"),
+    ]
+}
+
+/// What the commands that print lists can show next to the movies.
+fn shown_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::with_name("show-imdb").long("imdb")
+            .help("Show what the IMDb datasets say about the movies, see the enrich command")
+            .long_help("Show what the IMDb datasets say about the movies: tables get a line with the votes and \
+            directors, json an imdb_info field and csv its columns. See the enrich command"),
+        Arg::with_name("mark-seen").long("mark-seen")
+            .help("Flag the movies marked as seen")
+            .long_help("Flag the movies marked as seen: tables put (seen) before their titles, json and csv have a seen field"),
+    ]
+}
+
+/// The arguments that pick one of a movie's torrents.
+fn torrent_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::with_name("quality")
+            .long("quality")
+            .short('q')
+            .takes_value(true)
+            .case_insensitive(true)
+            .possible_values(["720p", "1080p", "2160p", "3D"])
+            .help("the wanted quality"),
+        Arg::with_name("type")
+            .long("type")
+            .short('t')
+            .takes_value(true)
+            .case_insensitive(true)
+            .possible_values(["web", "bluray"])
+            .help("the wanted rip type"),
+        min_size_arg(),
+        max_size_arg(),
+    ]
+}
+
+fn min_size_arg() -> Arg<'static> {
+    Arg::with_name("min-size")
+        .long("min-size")
+        .takes_value(true)
+        .help("Leave out torrents smaller than this")
+        .long_help(
+            "Leave out torrents smaller than this, e.g. 700MB or 1.5GiB.\n\
+        KB, MB, GB and TB are powers of 1000, KiB, MiB, GiB and TiB powers of 1024.\n\
+        yts writes GiB as GB, so a torrent it lists as 1.5 GB is 1.5GiB here, which is more than 1.5GB",
+        )
+        .value_parser(clap::value_parser!(ByteSize))
+}
+
+fn max_size_arg() -> Arg<'static> {
+    Arg::with_name("max-size")
+        .long("max-size")
+        .takes_value(true)
+        .help("Leave out torrents bigger than this")
+        .long_help(
+            "Leave out torrents bigger than this, e.g. 2GiB.\n\
+        KB, MB, GB and TB are powers of 1000, KiB, MiB, GiB and TiB powers of 1024.\n\
+        yts writes GiB as GB, so a torrent it lists as 1.5 GB is 1.5GiB here, which is more than 1.5GB",
+        )
+        .value_parser(clap::value_parser!(ByteSize))
+}
+
+fn mirror_arg() -> Arg<'static> {
+    Arg::with_name("mirror")
+        .takes_value(true)
+        .long("mirror")
+        .help("domain / mirror to use")
+        .long_help("will switch the domain to a mirror")
+        .possible_values(MIRRORS)
+        .default_value("yts.mx")
+}
+
+fn movies_arg() -> Arg<'static> {
+    Arg::with_name("movie")
+        .takes_value(true)
+        .multiple(true)
+        .required(true)
+        .help("yts ids or imdb codes")
+        .long_help("yts ids (e.g. 3175) or imdb codes (e.g. tt0068646)")
+}
+
+fn format_arg() -> Arg<'static> {
+    Arg::with_name("format")
+        .long("format")
+        .short('f')
+        .takes_value(true)
+        .possible_values(Format::VARIANTS)
+        .help("output format")
+        .long_help("Output format. Defaults to the one in the config file, or else to table")
+}
+
+/// Parses genres with [Genre::lookup], for its suggestions, while still completing them.
+#[derive(Clone)]
+struct GenreParser;
+
+impl TypedValueParser for GenreParser {
+    type Value = Genre;
+
+    fn parse_ref(&self, cmd: &Command, arg: Option<&Arg>, value: &OsStr) -> Result<Genre, Error> {
+        let value = value
+            .to_str()
+            .ok_or_else(|| Error::raw(ErrorKind::InvalidUtf8, "the genre isn't valid utf-8"))?;
+        Genre::lookup(value).map_err(|e| {
+            let arg = arg.map_or_else(|| "--genre".to_string(), Arg::to_string);
+            cmd.clone().error(
+                ErrorKind::InvalidValue,
+                format!("Invalid value '{}' for '{}': {}", value, arg, e),
+            )
+        })
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue<'static>> + '_>> {
+        Some(Box::new(
+            Genre::VARIANTS.iter().copied().map(PossibleValue::new),
+        ))
+    }
+}
+
+fn db_arg() -> Arg<'static> {
+    Arg::with_name("db").long("db").takes_value(true)
+        .help("path to the catalogue database")
+        .long_help("Path to the local catalogue database. Defaults to catalogue.sqlite in the user's data directory")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::yts::app::clap_app;
+    use clap_complete::Shell;
+    use yts::request::Genre;
+
+    #[test]
+    fn valid_app() {
+        clap_app().debug_assert();
+    }
+
+    #[test]
+    fn completes_genres() {
+        let mut app = clap_app();
+        let mut script = vec![];
+        clap_complete::generate(Shell::Fish, &mut app, "yts", &mut script);
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("-s g -l genre"));
+        assert!(script.contains("sci-fi"));
+        assert!(script.contains("download_count"));
+    }
+
+    #[test]
+    fn suggests_genres() {
+        let error = clap_app()
+            .try_get_matches_from(["yts", "list", "-g", "comdy"])
+            .unwrap_err();
+        assert!(error.to_string().contains("did you mean 'comedy'?"));
+        let matches = clap_app()
+            .try_get_matches_from(["yts", "list", "-g", "SCI-FI"])
+            .unwrap();
+        let list = matches.subcommand_matches("list").unwrap();
+        assert_eq!(list.get_one::<Genre>("genre"), Some(&Genre::SciFi));
+    }
+
+    #[test]
+    fn renders_man_page() {
+        let mut page = vec![];
+        clap_mangen::Man::new(clap_app()).render(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(".TH yts 1"));
+        assert!(page.contains("completions"));
+    }
 }
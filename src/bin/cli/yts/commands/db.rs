@@ -0,0 +1,47 @@
+use crate::cli::yts::commands::{fail, print, warn_unknown_to_imdb};
+use crate::cli::yts::input::{
+    catalogue_from_clap, list_req_from_clap, mirror_from_clap, post_filter_from_clap,
+    shown_from_clap,
+};
+use clap::ArgMatches;
+use yts::config::Config;
+
+pub fn sync(sync_matches: &ArgMatches, config: &Config) {
+    let mut catalogue =
+        catalogue_from_clap(sync_matches).unwrap_or_else(|e| fail("opening the catalogue", e));
+    let report = catalogue
+        .sync(
+            mirror_from_clap(sync_matches, config),
+            sync_matches.is_present("full"),
+        )
+        .unwrap_or_else(|e| fail("syncing the catalogue", e));
+    let total = catalogue
+        .movie_count()
+        .unwrap_or_else(|e| fail("reading the catalogue", e));
+
+    if let Some(page) = report.resumed {
+        println!("picked up the walk of the whole catalogue at page {}", page);
+    }
+    println!(
+        "synced {} movies and {} torrents from {} pages, {} movies in the catalogue",
+        report.movies, report.torrents, report.pages, total
+    );
+}
+
+pub fn db_query(query_matches: &ArgMatches, config: &Config) {
+    let catalogue =
+        catalogue_from_clap(query_matches).unwrap_or_else(|e| fail("opening the catalogue", e));
+    let request = list_req_from_clap(query_matches, config)
+        .unwrap_or_else(|e| fail("querying the catalogue", e));
+    let mut list = catalogue
+        .list(&request)
+        .unwrap_or_else(|e| fail("querying the catalogue", e));
+    let filter = post_filter_from_clap(query_matches, config)
+        .unwrap_or_else(|e| fail("querying the catalogue", e));
+    filter.apply(&mut list);
+    warn_unknown_to_imdb(&filter, &list);
+    let shown = shown_from_clap(query_matches, &filter)
+        .unwrap_or_else(|e| fail("querying the catalogue", e));
+
+    print(&list, shown.extras(), query_matches, config);
+}
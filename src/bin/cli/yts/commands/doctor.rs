@@ -0,0 +1,82 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::mirror_from_clap;
+use clap::ArgMatches;
+use serde_json::Value;
+use std::process::exit;
+use yts::config::Config;
+use yts::parse::schema::{self, Issue};
+use yts::request::{DetailsRequest, ListRequest, MovieRef};
+
+/// Checks a sample of api responses, or a saved one, against the schema and prints the drift.
+pub fn doctor_schema(schema_matches: &ArgMatches, config: &Config) {
+    let mirror = mirror_from_clap(schema_matches, config);
+    let mut checks: Vec<(String, Vec<Issue>)> = vec![];
+    if let Some(path) = schema_matches.value_of("file") {
+        let json: Value = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| fail("reading the response", e));
+        let kind = if json["data"].get("movie").is_some() {
+            &schema::DETAILS
+        } else {
+            &schema::LIST
+        };
+        checks.push((path.to_string(), schema::check(&json, kind)));
+    } else {
+        let mut request = ListRequest::new();
+        request
+            .limit(*schema_matches.get_one::<u8>("limit").unwrap())
+            .cache(&config.cache);
+        if let Some(mirror) = mirror {
+            request
+                .mirror(mirror)
+                .unwrap_or_else(|e| fail("fetching the sample", e));
+        }
+        let json = request
+            .execute_raw()
+            .unwrap_or_else(|e| fail("fetching the sample", e));
+        checks.push((
+            request.url().to_string(),
+            schema::check(&json, &schema::LIST),
+        ));
+
+        if let Some(id) = json["data"]["movies"][0]["id"].as_u64() {
+            let mut request = DetailsRequest::new(MovieRef::Id(id as u32));
+            request.cache(&config.cache);
+            if let Some(mirror) = mirror {
+                request
+                    .mirror(mirror)
+                    .unwrap_or_else(|e| fail("fetching the sample", e));
+            }
+            let json = request
+                .execute_raw()
+                .unwrap_or_else(|e| fail("fetching the sample", e));
+            checks.push((
+                request.url().to_string(),
+                schema::check(&json, &schema::DETAILS),
+            ));
+        }
+    }
+
+    let mut drifted = false;
+    for (source, issues) in checks {
+        if issues.is_empty() {
+            println!("{}: fits the schema", source);
+            continue;
+        }
+        drifted = true;
+        match issues.len() {
+            1 => println!("{}: 1 issue", source),
+            n => println!("{}: {} issues", source, n),
+        }
+        for (issue, count) in schema::drift(&issues) {
+            match count {
+                1 => println!("  {}", issue),
+                _ => println!("  {} ({} times)", issue, count),
+            }
+        }
+    }
+    if drifted {
+        exit(1);
+    }
+}
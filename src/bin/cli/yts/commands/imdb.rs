@@ -0,0 +1,44 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{catalogue_from_clap, imdb_path_from_clap};
+use clap::ArgMatches;
+use std::collections::HashSet;
+use std::path::Path;
+use yts::imdb::ImdbData;
+
+/// Joins the IMDb datasets with the movies of the catalogue and keeps the result for the lists.
+pub fn enrich(enrich_matches: &ArgMatches) {
+    let catalogue =
+        catalogue_from_clap(enrich_matches).unwrap_or_else(|e| fail("opening the catalogue", e));
+    let codes: HashSet<String> = catalogue
+        .movies()
+        .unwrap_or_else(|e| fail("reading the catalogue", e))
+        .into_iter()
+        .filter_map(|movie| movie.imdb_code)
+        .collect();
+    if codes.is_empty() {
+        fail(
+            "enriching the catalogue",
+            "the catalogue is empty, run yts sync first",
+        );
+    }
+
+    let dir = Path::new(enrich_matches.value_of("imdb-datasets").unwrap());
+    let data = ImdbData::from_datasets(dir, &codes)
+        .unwrap_or_else(|e| fail("reading the IMDb datasets", e));
+    let path =
+        imdb_path_from_clap(enrich_matches).unwrap_or_else(|e| fail("saving the IMDb data", e));
+    data.save(&path)
+        .unwrap_or_else(|e| fail("saving the IMDb data", e));
+
+    let with_directors = data
+        .titles
+        .values()
+        .filter(|info| !info.directors.is_empty())
+        .count();
+    println!(
+        "found {} of {} catalogue movies in the IMDb datasets, {} with directors",
+        data.len(),
+        codes.len(),
+        with_directors
+    );
+}
@@ -0,0 +1,80 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{catalogue_from_clap, mirror_from_clap, watchlist_path_from_clap};
+use clap::ArgMatches;
+use yts::config::Config;
+use yts::import::{lookup_entry, read_export, report_table, resolve_entry, Resolution, Service};
+use yts::lists::MovieList;
+use yts::lookup::resolve_all;
+use yts::titles::TitleMatcher;
+
+/// Adds the movies of a Letterboxd, Trakt or IMDb export to the watchlist and reports how each
+/// entry was resolved.
+pub fn import(import_matches: &ArgMatches, config: &Config) {
+    let service: Service = import_matches.value_of("from").unwrap().parse().unwrap();
+    let export = std::fs::File::open(import_matches.value_of("file").unwrap())
+        .unwrap_or_else(|e| fail("reading the export", e));
+    let (entries, others): (Vec<_>, Vec<_>) = read_export(service, export)
+        .unwrap_or_else(|e| fail("reading the export", e))
+        .into_iter()
+        .partition(|entry| entry.is_movie());
+
+    let catalogue = import_matches.is_present("offline").then(|| {
+        catalogue_from_clap(import_matches)
+            .and_then(|catalogue| catalogue.movies())
+            .unwrap_or_else(|e| fail("opening the catalogue", e))
+    });
+    let mirror = mirror_from_clap(import_matches, config);
+    let jobs = *import_matches.get_one::<u8>("jobs").unwrap() as usize;
+    let matcher = catalogue.as_deref().map(TitleMatcher::new);
+    let results = resolve_all(&entries, jobs, |entry| match &matcher {
+        Some(matcher) => Ok(resolve_entry(entry, matcher)),
+        None => lookup_entry(entry, &config.cache, mirror),
+    });
+    let results: Vec<_> = entries.into_iter().zip(results).collect();
+
+    let path = watchlist_path_from_clap(import_matches)
+        .unwrap_or_else(|e| fail("loading the watchlist", e));
+    let mut watchlist = MovieList::load(&path).unwrap_or_else(|e| fail("loading the watchlist", e));
+    let (mut added, mut known, mut ambiguous, mut missing, mut failed) = (0, 0, 0, 0, 0);
+    for (_, result) in &results {
+        match result {
+            Ok(Resolution::Matched(movie)) if watchlist.contains(movie) => known += 1,
+            Ok(Resolution::Matched(movie)) => {
+                if watchlist.add((**movie).clone()) {
+                    added += 1;
+                }
+            }
+            Ok(Resolution::Ambiguous(_)) => ambiguous += 1,
+            Ok(Resolution::Missing) => missing += 1,
+            Err(_) => failed += 1,
+        }
+    }
+    if !import_matches.is_present("dry-run") {
+        watchlist
+            .save(&path)
+            .unwrap_or_else(|e| fail("saving the watchlist", e));
+    }
+
+    report_table(&results).printstd();
+    println!(
+        "\n{} {} to the watchlist, {} already on it, {} ambiguous, {} missing",
+        if import_matches.is_present("dry-run") {
+            "would add"
+        } else {
+            "added"
+        },
+        added,
+        known,
+        ambiguous,
+        missing
+    );
+    if failed > 0 {
+        println!(
+            "{} couldn't be looked up, importing again retries them",
+            failed
+        );
+    }
+    if !others.is_empty() {
+        println!("skipped {} shows and episodes", others.len());
+    }
+}
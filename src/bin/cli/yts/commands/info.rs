@@ -0,0 +1,42 @@
+use crate::cli::yts::app::clap_app;
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{catalogue_from_clap, data_path_from_clap};
+use clap::ArgMatches;
+use clap_complete::Shell;
+use strum::IntoEnumIterator;
+use yts::request::Genre;
+
+/// Prints the genres, with their movie counts if there's a catalogue to count in.
+pub fn genres(genres_matches: &ArgMatches) {
+    let path = data_path_from_clap(genres_matches, "db", "catalogue.sqlite")
+        .unwrap_or_else(|e| fail("opening the catalogue", e));
+    let counts = if path.exists() {
+        catalogue_from_clap(genres_matches)
+            .and_then(|catalogue| catalogue.genre_counts())
+            .unwrap_or_else(|e| fail("reading the catalogue", e))
+    } else {
+        vec![]
+    };
+
+    for genre in Genre::iter().filter(|genre| *genre != Genre::All) {
+        let name: &str = genre.into();
+        match counts.iter().find(|(g, _)| g.eq_ignore_ascii_case(name)) {
+            Some((_, count)) => println!("{:<12} {:>6}", name, count),
+            None if counts.is_empty() => println!("{}", name),
+            None => println!("{:<12} {:>6}", name, 0),
+        }
+    }
+}
+
+pub fn completions(completions_matches: &ArgMatches) {
+    let shell = *completions_matches.get_one::<Shell>("shell").unwrap();
+    let mut app = clap_app();
+    let name = app.get_name().to_string();
+    clap_complete::generate(shell, &mut app, name, &mut std::io::stdout());
+}
+
+pub fn man() {
+    clap_mangen::Man::new(clap_app())
+        .render(&mut std::io::stdout())
+        .unwrap_or_else(|e| fail("printing the man page", e));
+}
@@ -0,0 +1,64 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{
+    catalogue_from_clap, library_path_from_clap, watchlist_path_from_clap,
+};
+use clap::ArgMatches;
+use std::path::Path;
+use yts::library::Library;
+use yts::lists::MovieList;
+use yts::parse::api::Movie;
+
+pub fn library_scan(scan_matches: &ArgMatches) {
+    let movies = catalogue_from_clap(scan_matches)
+        .and_then(|catalogue| catalogue.movies())
+        .unwrap_or_else(|e| fail("reading the catalogue", e));
+    if movies.is_empty() {
+        eprintln!("the catalogue is empty, run yts sync first");
+    }
+    let library = Library::scan(Path::new(scan_matches.value_of("dir").unwrap()), &movies)
+        .unwrap_or_else(|e| fail("scanning the library", e));
+    let watchlist = watchlist_path_from_clap(scan_matches)
+        .and_then(|path| MovieList::load(&path))
+        .unwrap_or_else(|e| fail("loading the watchlist", e));
+
+    let missing = watchlist
+        .movies()
+        .into_iter()
+        .filter(|movie| !library.contains(movie))
+        .collect::<Vec<Movie>>();
+    if !missing.is_empty() {
+        println!("watchlist titles that are not on disk:");
+        for movie in &missing {
+            println!("  {}", movie.title_long());
+        }
+    }
+
+    let upgrades = library.upgrades(&movies);
+    if !upgrades.is_empty() {
+        println!("available in a better quality:");
+        for upgrade in &upgrades {
+            println!(
+                "  {}: {} on disk, {} available",
+                upgrade.movie.title_long(),
+                upgrade.owned.as_deref().unwrap_or("unknown quality"),
+                upgrade.available
+            );
+        }
+    }
+
+    if !library.unmatched.is_empty() {
+        println!("not matched:");
+        for path in &library.unmatched {
+            println!("  {}", path.display());
+        }
+    }
+
+    println!(
+        "found {} movies, {} files weren't matched",
+        library.owned.len(),
+        library.unmatched.len()
+    );
+    library_path_from_clap(scan_matches)
+        .and_then(|path| library.save(&path))
+        .unwrap_or_else(|e| fail("saving the library", e));
+}
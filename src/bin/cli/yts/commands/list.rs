@@ -0,0 +1,69 @@
+use crate::cli::yts::commands::{fail, print, warn_unknown_to_imdb};
+use crate::cli::yts::input::{
+    catalogue_from_clap, list_req_from_clap, mirror_from_clap, post_filter_from_clap,
+    shown_from_clap,
+};
+use clap::ArgMatches;
+use yts::config::Config;
+use yts::parse::api::ListResponse;
+use yts::request::ListRequest;
+use yts::search::Index;
+use yts::view::Extras;
+
+pub fn list(list_matches: &ArgMatches, config: &Config) {
+    let mut list: ListResponse = if list_matches.is_present("response-mock-file") {
+        let json = std::fs::read_to_string(list_matches.value_of("response-mock-file").unwrap())
+            .expect("can't read mock json data");
+        serde_json::from_str(json.as_str()).expect("expected a parsed response")
+    } else {
+        let request = list_req_from_clap(list_matches, config)
+            .unwrap_or_else(|e| fail("retrieving the list", e));
+        request
+            .execute()
+            .unwrap_or_else(|e| fail("retrieving the list", e))
+    };
+    let filter = post_filter_from_clap(list_matches, config)
+        .unwrap_or_else(|e| fail("retrieving the list", e));
+    filter.apply(&mut list);
+    warn_unknown_to_imdb(&filter, &list);
+    let shown =
+        shown_from_clap(list_matches, &filter).unwrap_or_else(|e| fail("retrieving the list", e));
+
+    print(&list, shown.extras(), list_matches, config);
+}
+
+pub fn search(search_matches: &ArgMatches, config: &Config) {
+    let query = search_matches
+        .values_of("query")
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let limit = *search_matches.get_one::<u8>("limit").unwrap();
+
+    let list: ListResponse = if search_matches.is_present("offline") {
+        let catalogue = catalogue_from_clap(search_matches)
+            .unwrap_or_else(|e| fail("opening the catalogue", e));
+        let index = catalogue
+            .movies()
+            .unwrap_or_else(|e| fail("reading the catalogue", e))
+            .into_iter()
+            .collect::<Index>();
+        index
+            .search(&query, limit.into())
+            .into_iter()
+            .map(|hit| hit.movie.clone())
+            .collect::<Vec<_>>()
+            .into()
+    } else {
+        let mut request = ListRequest::new();
+        request.query_term(query).limit(limit).cache(&config.cache);
+        if let Some(mirror) = mirror_from_clap(search_matches, config) {
+            request
+                .mirror(mirror)
+                .unwrap_or_else(|e| fail("searching", e));
+        }
+        request.execute().unwrap_or_else(|e| fail("searching", e))
+    };
+
+    print(&list, Extras::default(), search_matches, config);
+}
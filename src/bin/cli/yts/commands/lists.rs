@@ -0,0 +1,114 @@
+use crate::cli::yts::commands::{details, fail, lookup, print};
+use crate::cli::yts::input::{movie_refs_from_clap, seen_path_from_clap, watchlist_path_from_clap};
+use clap::ArgMatches;
+use yts::config::Config;
+use yts::lists::MovieList;
+use yts::request::Quality;
+use yts::view::Extras;
+
+pub fn watchlist(watchlist_matches: &ArgMatches, config: &Config) {
+    let path = watchlist_path_from_clap(watchlist_matches)
+        .unwrap_or_else(|e| fail("loading the watchlist", e));
+    let mut watchlist = MovieList::load(&path).unwrap_or_else(|e| fail("loading the watchlist", e));
+
+    match watchlist_matches.subcommand() {
+        Some(("add", add_matches)) => {
+            for movie in lookup(add_matches, config) {
+                println!("added {} to the watchlist", movie.title_long());
+                watchlist.add(movie);
+            }
+        }
+        Some(("rm", rm_matches)) => remove(&mut watchlist, rm_matches, "watchlist"),
+        Some(("ls", ls_matches)) => print(
+            &watchlist.movies().into(),
+            Extras::default(),
+            ls_matches,
+            config,
+        ),
+        Some(("check", check_matches)) => {
+            let quality = check_matches
+                .value_of("quality")
+                .or(config.list.quality.as_deref())
+                .unwrap_or("1080p");
+            let quality: Quality = quality
+                .parse()
+                .unwrap_or_else(|_| fail("checking the watchlist", "invalid quality"));
+            let mut available = 0;
+
+            for entry in watchlist.movies() {
+                let movie = match entry.id {
+                    Some(id) => details(id.into(), check_matches, config).unwrap_or(entry),
+                    None => entry,
+                };
+                if movie.has_quality(quality) {
+                    available += 1;
+                    println!(
+                        "{} is available in {}: {}",
+                        movie.title_long(),
+                        quality,
+                        movie.url()
+                    );
+                } else {
+                    println!("{} has no {} torrent yet", movie.title_long(), quality);
+                }
+                watchlist.add(movie);
+            }
+            println!(
+                "{} of {} watchlist titles are available in {}",
+                available,
+                watchlist.len(),
+                quality
+            );
+        }
+        _ => {}
+    }
+
+    watchlist
+        .save(&path)
+        .unwrap_or_else(|e| fail("saving the watchlist", e));
+}
+
+pub fn seen(seen_matches: &ArgMatches, config: &Config) {
+    let path =
+        seen_path_from_clap(seen_matches).unwrap_or_else(|e| fail("loading the seen list", e));
+    let mut seen = MovieList::load(&path).unwrap_or_else(|e| fail("loading the seen list", e));
+
+    match seen_matches.subcommand() {
+        Some(("add", add_matches)) => {
+            let watchlist_path = watchlist_path_from_clap(add_matches)
+                .unwrap_or_else(|e| fail("loading the watchlist", e));
+            let mut watchlist = MovieList::load(&watchlist_path)
+                .unwrap_or_else(|e| fail("loading the watchlist", e));
+
+            for movie in lookup(add_matches, config) {
+                println!("marked {} as seen", movie.title_long());
+                if let Some(id) = movie.id {
+                    watchlist.remove(&id.into());
+                }
+                seen.add(movie);
+            }
+
+            watchlist
+                .save(&watchlist_path)
+                .unwrap_or_else(|e| fail("saving the watchlist", e));
+        }
+        Some(("rm", rm_matches)) => remove(&mut seen, rm_matches, "seen list"),
+        Some(("ls", ls_matches)) => {
+            print(&seen.movies().into(), Extras::default(), ls_matches, config)
+        }
+        _ => {}
+    }
+
+    seen.save(&path)
+        .unwrap_or_else(|e| fail("saving the seen list", e));
+}
+
+pub fn remove(list: &mut MovieList, matches: &ArgMatches, name: &str) {
+    for movie in movie_refs_from_clap(matches).unwrap_or_else(|e| fail("reading the arguments", e))
+    {
+        match list.remove(&movie) {
+            Some(entry) => println!("removed {} from the {}", entry.movie.title_long(), name),
+            None => eprintln!("{} is not on the {}", movie, name),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::cli::yts::commands::{fail, print};
+use crate::cli::yts::input::mirror_from_clap;
+use clap::ArgMatches;
+use log::warn;
+use std::io::Read;
+use std::process::exit;
+use yts::config::Config;
+use yts::lookup::{read_refs, resolve, resolve_all};
+use yts::view::Extras;
+
+/// Looks up the ids of a file and prints the movies, reporting the ones yts doesn't have.
+pub fn lookup_file(lookup_matches: &ArgMatches, config: &Config) {
+    let path = lookup_matches.value_of("file").unwrap();
+    let text = if path == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        std::fs::read_to_string(path)
+    }
+    .unwrap_or_else(|e| fail("reading the ids", e));
+    let refs = read_refs(&text).unwrap_or_else(|e| fail("reading the ids", e));
+
+    let mirror = mirror_from_clap(lookup_matches, config);
+    let jobs = *lookup_matches.get_one::<u8>("jobs").unwrap() as usize;
+    let results = resolve_all(&refs, jobs, |movie| resolve(movie, &config.cache, mirror));
+
+    let mut movies = vec![];
+    let mut missing = vec![];
+    let mut failed = vec![];
+    for (movie, result) in refs.iter().zip(results) {
+        match result {
+            Ok(Some(found)) => movies.push(found),
+            Ok(None) => missing.push(movie.to_string()),
+            Err(e) => {
+                warn!("can't look {} up: {}", movie, e);
+                failed.push(movie.to_string());
+            }
+        }
+    }
+
+    print(&movies.into(), Extras::default(), lookup_matches, config);
+    if !missing.is_empty() {
+        eprintln!("yts doesn't have {}", missing.join(", "));
+    }
+    if !failed.is_empty() {
+        eprintln!("can't look {} up", failed.join(", "));
+    }
+    if !missing.is_empty() || !failed.is_empty() {
+        eprintln!(
+            "found {} of {} movies",
+            refs.len() - missing.len() - failed.len(),
+            refs.len()
+        );
+        exit(1);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::cli::yts::commands::{details, fail};
+use crate::cli::yts::input::movie_refs_from_clap;
+use clap::ArgMatches;
+use std::path::Path;
+use std::process::exit;
+use yts::artwork::{ArtCache, ArtKind, ArtSize};
+use yts::config::Config;
+use yts::nfo::movie_nfo;
+
+pub fn nfo(nfo_matches: &ArgMatches, config: &Config) {
+    let movie = movie_refs_from_clap(nfo_matches)
+        .unwrap_or_else(|e| fail("reading the arguments", e))
+        .remove(0);
+    let movie = details(movie, nfo_matches, config).unwrap_or_else(|| exit(1));
+    let path = Path::new(nfo_matches.value_of("out").unwrap_or(".")).join("movie.nfo");
+
+    std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| std::fs::write(&path, movie_nfo(&movie)))
+        .unwrap_or_else(|e| fail("writing the nfo", e));
+    println!("wrote {}", path.display());
+}
+
+pub fn art(art_matches: &ArgMatches, config: &Config) {
+    let kind: ArtKind = art_matches
+        .value_of("kind")
+        .unwrap()
+        .parse()
+        .unwrap_or_else(|e| fail("reading the arguments", e));
+    let size: ArtSize = art_matches
+        .value_of("size")
+        .unwrap()
+        .parse()
+        .unwrap_or_else(|e| fail("reading the arguments", e));
+    let movie = movie_refs_from_clap(art_matches)
+        .unwrap_or_else(|e| fail("reading the arguments", e))
+        .remove(0);
+    let movie = details(movie, art_matches, config).unwrap_or_else(|| exit(1));
+    let url = movie.artwork(kind, size).unwrap_or_else(|| {
+        fail(
+            "downloading artwork",
+            format!("{} has no {}", movie.title_long(), kind),
+        )
+    });
+
+    let path = ArtCache::from_settings(&config.art)
+        .and_then(|cache| cache.fetch(url))
+        .unwrap_or_else(|e| fail("downloading artwork", e));
+    println!("{}", path.display());
+}
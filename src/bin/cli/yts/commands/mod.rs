@@ -0,0 +1,85 @@
+pub mod db;
+pub mod doctor;
+pub mod imdb;
+pub mod import;
+pub mod info;
+pub mod library;
+pub mod list;
+pub mod lists;
+pub mod lookup;
+pub mod metadata;
+pub mod snapshot;
+pub mod stats;
+pub mod torrents;
+pub mod tui;
+pub mod watch;
+
+use crate::cli::yts::input::{mirror_from_clap, movie_refs_from_clap};
+use crate::cli::yts::output::{format_from_clap, print_list};
+use clap::ArgMatches;
+use log::{error, warn};
+use std::fmt::Display;
+use std::process::exit;
+use yts::config::Config;
+use yts::filter::PostFilter;
+use yts::parse::api::{ListResponse, Movie};
+use yts::request::{DetailsRequest, MovieRef};
+use yts::view::Extras;
+
+/// Prints a short message for the user and logs the actual error before exiting.
+pub fn fail(message: &str, e: impl Display) -> ! {
+    eprintln!("encountered a problem while {}", message);
+    error!("{}: {}", message, e);
+    exit(1);
+}
+
+/// Says how many movies the IMDb filters couldn't judge, as they're kept rather than dropped.
+pub fn warn_unknown_to_imdb(filter: &PostFilter, list: &ListResponse) {
+    let unknown = filter.unknown_to_imdb(list.movies());
+    if unknown > 0 {
+        eprintln!(
+            "{} of the movies have no IMDb data and weren't filtered by it, \
+             yts sync && yts enrich catches up on them",
+            unknown
+        );
+    }
+}
+
+/// Prints the list in the format asked for.
+pub fn print(list: &ListResponse, extras: Extras, matches: &ArgMatches, config: &Config) {
+    let format = format_from_clap(matches, config).unwrap_or_else(|e| fail("printing", e));
+    print_list(list, extras, format).unwrap_or_else(|e| fail("printing", e));
+}
+
+/// Looks up the movies given as arguments, skipping the ones yts doesn't have.
+pub fn lookup(matches: &ArgMatches, config: &Config) -> Vec<Movie> {
+    movie_refs_from_clap(matches)
+        .unwrap_or_else(|e| fail("reading the arguments", e))
+        .into_iter()
+        .filter_map(|movie| details(movie, matches, config))
+        .collect()
+}
+
+/// The details of a movie, or None (with a warning) if they can't be had.
+pub fn details(movie: MovieRef, matches: &ArgMatches, config: &Config) -> Option<Movie> {
+    let mut request = DetailsRequest::new(movie.clone());
+    request.cache(&config.cache);
+    if let Some(mirror) = mirror_from_clap(matches, config) {
+        request
+            .mirror(mirror)
+            .unwrap_or_else(|e| fail("looking movies up", e));
+    }
+
+    match request.execute() {
+        Ok(Some(movie)) => Some(movie),
+        Ok(None) => {
+            eprintln!("yts doesn't have {}", movie);
+            None
+        }
+        Err(e) => {
+            eprintln!("can't look {} up", movie);
+            warn!("can't look {} up: {}", movie, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{catalogue_from_clap, list_req_from_clap, post_filter_from_clap};
+use crate::cli::yts::output::{format_from_clap, Format};
+use clap::ArgMatches;
+use std::path::Path;
+use yts::config::Config;
+use yts::snapshot::{changes_table, Scope, Snapshot};
+
+pub fn snapshot_save(save_matches: &ArgMatches, config: &Config) {
+    let scope = if save_matches.is_present("all") {
+        Scope::Catalogue
+    } else {
+        Scope::Page
+    };
+    let movies = if scope == Scope::Catalogue {
+        catalogue_from_clap(save_matches)
+            .and_then(|catalogue| catalogue.movies())
+            .unwrap_or_else(|e| fail("reading the catalogue", e))
+    } else {
+        let request = list_req_from_clap(save_matches, config)
+            .unwrap_or_else(|e| fail("retrieving the list", e));
+        let mut list = if save_matches.is_present("offline") {
+            catalogue_from_clap(save_matches)
+                .and_then(|catalogue| catalogue.list(&request))
+                .unwrap_or_else(|e| fail("querying the catalogue", e))
+        } else {
+            request
+                .execute()
+                .unwrap_or_else(|e| fail("retrieving the list", e))
+        };
+        post_filter_from_clap(save_matches, config)
+            .unwrap_or_else(|e| fail("retrieving the list", e))
+            .apply(&mut list);
+        list.data.and_then(|d| d.movies).unwrap_or_default()
+    };
+
+    let path = Path::new(save_matches.value_of("file").unwrap());
+    let count = movies.len();
+    Snapshot::new(movies, scope)
+        .save(path)
+        .unwrap_or_else(|e| fail("saving the snapshot", e));
+    println!("saved {} movies to {}", count, path.display());
+}
+
+pub fn diff(diff_matches: &ArgMatches, config: &Config) {
+    let load = |name| {
+        Snapshot::load(Path::new(diff_matches.value_of(name).unwrap()))
+            .unwrap_or_else(|e| fail("loading the snapshots", e))
+    };
+    let (old, new) = (load("old"), load("new"));
+    let changes = old.diff(&new);
+    if !old.shows_removals(&new) {
+        eprintln!("removed movies are only reported between snapshots of the whole catalogue (snapshot save --all)");
+    }
+
+    match format_from_clap(diff_matches, config).unwrap_or_else(|e| fail("printing the diff", e)) {
+        Format::Table if changes.is_empty() => println!("no changes"),
+        Format::Table => changes_table(&changes).printstd(),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&changes).unwrap_or_else(|e| fail("printing the diff", e))
+        ),
+        Format::Csv => changes_table(&changes)
+            .to_csv(std::io::stdout())
+            .map(|_| ())
+            .unwrap_or_else(|e| fail("printing the diff", e)),
+    }
+}
@@ -0,0 +1,56 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{catalogue_from_clap, list_req_from_clap, post_filter_from_clap};
+use crate::cli::yts::output::{format_from_clap, Format};
+use clap::ArgMatches;
+use yts::config::Config;
+use yts::parse::api::ListResponse;
+use yts::stats::Stats;
+
+/// Stats over the local catalogue, or over pages of the api with --online.
+pub fn stats(stats_matches: &ArgMatches, config: &Config) {
+    let mut request =
+        list_req_from_clap(stats_matches, config).unwrap_or_else(|e| fail("building the query", e));
+    let filter = post_filter_from_clap(stats_matches, config)
+        .unwrap_or_else(|e| fail("building the query", e));
+    let movies = if stats_matches.is_present("online") {
+        let pages = stats_matches.get_one::<u32>("pages").copied().unwrap_or(1);
+        let first = stats_matches
+            .get_one::<u16>("page")
+            .map_or(1, |page| *page as u32);
+        let mut movies = vec![];
+        for page in (first..).take(pages as usize) {
+            request.page(page);
+            let mut list = request
+                .execute()
+                .unwrap_or_else(|e| fail("retrieving the movies", e));
+            let count = list.movies().len() as u64;
+            let limit = list.data.as_ref().and_then(|d| d.limit).unwrap_or(0) as u64;
+            filter.apply(&mut list);
+            movies.extend(list.data.and_then(|d| d.movies).unwrap_or_default());
+            if count == 0 || count < limit {
+                break;
+            }
+        }
+        movies
+    } else {
+        let mut list: ListResponse = catalogue_from_clap(stats_matches)
+            .and_then(|catalogue| catalogue.matching(&request))
+            .unwrap_or_else(|e| fail("reading the catalogue", e))
+            .into();
+        filter.apply(&mut list);
+        list.data.and_then(|d| d.movies).unwrap_or_default()
+    };
+
+    let stats = Stats::new(&movies, *stats_matches.get_one::<u16>("year-span").unwrap());
+    match format_from_clap(stats_matches, config).unwrap_or_else(|e| fail("printing the stats", e))
+    {
+        Format::Table => print!("{}", stats),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).unwrap_or_else(|e| fail("printing the stats", e))
+        ),
+        Format::Csv => stats
+            .write_csv(std::io::stdout())
+            .unwrap_or_else(|e| fail("printing the stats", e)),
+    }
+}
@@ -0,0 +1,239 @@
+use crate::cli::yts::commands::{details, fail};
+use crate::cli::yts::input::{
+    catalogue_from_clap, data_path_from_clap, mirror_from_clap, movie_refs_from_clap,
+};
+use clap::ArgMatches;
+use log::warn;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use yts::config::Config;
+use yts::download::{best_torrent, download, fetch, file_name, torrent_url, DEFAULT_NAME_TEMPLATE};
+use yts::parse::api::Torrent;
+use yts::request::Quality;
+use yts::size::{ByteSize, SizeRange};
+use yts::torrent_client::Source;
+use yts::torrent_file::TorrentFile;
+
+/// The quality, type and size range asked for with the torrent args.
+pub fn torrent_choice(matches: &ArgMatches) -> (Option<Quality>, Option<&str>, SizeRange) {
+    let quality = matches
+        .value_of("quality")
+        .map(|q| q.parse::<Quality>())
+        .transpose()
+        .unwrap_or_else(|e| fail("reading the arguments", e));
+    let sizes = SizeRange {
+        min: matches.get_one::<ByteSize>("min-size").copied(),
+        max: matches.get_one::<ByteSize>("max-size").copied(),
+    };
+    (quality, matches.value_of("type"), sizes)
+}
+
+pub fn get(get_matches: &ArgMatches, config: &Config) {
+    let (quality, ty_pe, sizes) = torrent_choice(get_matches);
+    let dir = get_matches
+        .value_of("dir")
+        .map(PathBuf::from)
+        .or_else(|| config.get.dir.clone())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let template = get_matches
+        .value_of("name")
+        .or(config.get.name.as_deref())
+        .unwrap_or(DEFAULT_NAME_TEMPLATE);
+    let mirror = mirror_from_clap(get_matches, config);
+    let mut failed = false;
+    let mut saved: Vec<Option<ByteSize>> = vec![];
+
+    for movie in
+        movie_refs_from_clap(get_matches).unwrap_or_else(|e| fail("reading the arguments", e))
+    {
+        let mut movie = match details(movie, get_matches, config) {
+            Some(movie) => movie,
+            None => {
+                failed = true;
+                continue;
+            }
+        };
+        movie.retain_sizes(&sizes);
+        let torrent = match best_torrent(&movie, quality, ty_pe) {
+            Some(torrent) => torrent,
+            None => {
+                eprintln!("{} has no matching torrent", movie.title_long());
+                failed = true;
+                continue;
+            }
+        };
+        let url = torrent_url(torrent, mirror).unwrap_or_else(|e| fail("downloading", e));
+        let name = file_name(template, &movie, torrent);
+
+        if get_matches.is_present("dry-run") {
+            println!("would save {} as {}", url, dir.join(name).display());
+            saved.push(torrent.bytes());
+            continue;
+        }
+        let force = get_matches.is_present("force");
+        match download(&url, &dir, &name, force, |path| verify(path, torrent)) {
+            Ok(path) => {
+                println!("saved {}", path.display());
+                saved.push(torrent.bytes());
+            }
+            Err(e) => {
+                eprintln!("can't download the torrent for {}", movie.title_long());
+                warn!("can't download {}: {}", url, e);
+                failed = true;
+            }
+        }
+    }
+
+    if saved.len() > 1 {
+        let total: ByteSize = saved.iter().flatten().copied().sum();
+        let verb = if get_matches.is_present("dry-run") {
+            "would get"
+        } else {
+            "got"
+        };
+        println!("{} {} torrents, {} of movies", verb, saved.len(), total);
+    }
+    if failed {
+        exit(1);
+    }
+}
+
+pub fn send(send_matches: &ArgMatches, config: &Config) {
+    let (quality, ty_pe, sizes) = torrent_choice(send_matches);
+    let settings = config
+        .client(send_matches.value_of("to"))
+        .unwrap_or_else(|e| fail("picking the client", e));
+    let client = settings
+        .client()
+        .unwrap_or_else(|e| fail("picking the client", e));
+    let mut options = settings.options();
+    if let Some(category) = send_matches.value_of("category") {
+        options.category = Some(category.to_string());
+    }
+    if let Some(save_path) = send_matches.value_of("save-path") {
+        options.save_path = Some(save_path.to_string());
+    }
+    options.paused |= send_matches.is_present("paused");
+    let mirror = mirror_from_clap(send_matches, config);
+    let mut failed = false;
+
+    for movie in
+        movie_refs_from_clap(send_matches).unwrap_or_else(|e| fail("reading the arguments", e))
+    {
+        let mut movie = match details(movie, send_matches, config) {
+            Some(movie) => movie,
+            None => {
+                failed = true;
+                continue;
+            }
+        };
+        movie.retain_sizes(&sizes);
+        let torrent = match best_torrent(&movie, quality, ty_pe) {
+            Some(torrent) => torrent,
+            None => {
+                eprintln!("{} has no matching torrent", movie.title_long());
+                failed = true;
+                continue;
+            }
+        };
+
+        let source = if send_matches.is_present("file") {
+            torrent_url(torrent, mirror)
+                .and_then(|url| fetch(&url))
+                .and_then(|bytes| {
+                    TorrentFile::parse(&bytes)?.verify(torrent)?;
+                    Ok(Source::File {
+                        name: file_name(DEFAULT_NAME_TEMPLATE, &movie, torrent),
+                        bytes,
+                    })
+                })
+        } else {
+            torrent
+                .magnet(&movie.title_long())
+                .map(Source::Magnet)
+                .ok_or_else(|| "the torrent has no hash".into())
+        };
+
+        match source.and_then(|source| client.add(&source, &options)) {
+            Ok(()) => println!(
+                "sent the {} torrent of {}",
+                torrent.quality.as_deref().unwrap_or_default(),
+                movie.title_long()
+            ),
+            Err(e) => {
+                eprintln!("can't send the torrent of {}", movie.title_long());
+                warn!("can't send the torrent of {}: {}", movie.title_long(), e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+}
+
+/// Checks a downloaded .torrent file against the torrent the api describes.
+pub fn verify(path: &Path, torrent: &Torrent) -> Result<(), Box<dyn Error>> {
+    TorrentFile::load(path)
+        .and_then(|file| file.verify(torrent))
+        .map_err(|e| format!("the download doesn't match the api: {}", e).into())
+}
+
+pub fn inspect(inspect_matches: &ArgMatches, config: &Config) {
+    let file = TorrentFile::load(Path::new(inspect_matches.value_of("file").unwrap()))
+        .unwrap_or_else(|e| fail("reading the torrent file", e));
+    print!("{}", file);
+
+    let movie = match movie_refs_from_clap(inspect_matches)
+        .unwrap_or_else(|e| fail("reading the arguments", e))
+        .pop()
+    {
+        Some(movie) => details(movie, inspect_matches, config).unwrap_or_else(|| exit(1)),
+        None => {
+            let known = data_path_from_clap(inspect_matches, "db", "catalogue.sqlite")
+                .map(|path| path.exists())
+                .unwrap_or(false);
+            let movie = if known {
+                catalogue_from_clap(inspect_matches)
+                    .and_then(|catalogue| catalogue.movie_by_hash(&file.info_hash))
+                    .unwrap_or_else(|e| fail("reading the catalogue", e))
+            } else {
+                None
+            };
+            match movie {
+                Some(movie) => movie,
+                None => {
+                    println!("not checked: the info-hash is not in the catalogue, try --movie");
+                    return;
+                }
+            }
+        }
+    };
+
+    let torrent = movie.torrents.iter().flatten().find(|t| {
+        t.hash
+            .as_deref()
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(&file.info_hash))
+    });
+    match torrent {
+        Some(torrent) => {
+            file.verify(torrent)
+                .unwrap_or_else(|e| fail("checking the torrent file", e));
+            println!(
+                "matches the {} {} torrent of {}",
+                torrent.quality.as_deref().unwrap_or_default(),
+                torrent.ty_pe.as_deref().unwrap_or_default(),
+                movie.title_long()
+            );
+        }
+        None => fail(
+            "checking the torrent file",
+            format!(
+                "the info-hash matches none of the torrents of {}",
+                movie.title_long()
+            ),
+        ),
+    }
+}
@@ -0,0 +1,83 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::commands::torrents::verify;
+use crate::cli::yts::input::{
+    list_req_from_clap, mirror_from_clap, post_filter_from_clap, watchlist_path_from_clap,
+};
+use crate::cli::yts::tui::{self, Action, App};
+use clap::ArgMatches;
+use std::error::Error;
+use std::path::PathBuf;
+use yts::config::Config;
+use yts::download::{best_torrent, download, file_name, torrent_url, DEFAULT_NAME_TEMPLATE};
+use yts::lists::MovieList;
+use yts::parse::api::ListResponse;
+
+pub fn tui(tui_matches: &ArgMatches, config: &Config) {
+    let filter = post_filter_from_clap(tui_matches, config)
+        .unwrap_or_else(|e| fail("building the query", e));
+    let mock = tui_matches.value_of("response-mock-file");
+    let fetch = move |page: u32, search: Option<&str>| -> Result<ListResponse, Box<dyn Error>> {
+        let mut list: ListResponse = match mock {
+            // the mock is the only page there is
+            Some(path) if page == 1 => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            Some(_) => Vec::new().into(),
+            None => {
+                let mut request = list_req_from_clap(tui_matches, config)?;
+                request.page(page);
+                if let Some(search) = search {
+                    request.query_term(search.to_string());
+                }
+                request.execute()?
+            }
+        };
+        filter.apply(&mut list);
+        Ok(list)
+    };
+    let mut app = App::new(Box::new(fetch));
+
+    tui::run(&mut app, &mut |action| {
+        tui_action(action, tui_matches, config)
+    })
+    .unwrap_or_else(|e| fail("running the interface", e));
+}
+
+/// Does what was asked for in the interface, returning what to tell the user.
+pub fn tui_action(
+    action: Action,
+    matches: &ArgMatches,
+    config: &Config,
+) -> Result<String, Box<dyn Error>> {
+    match action {
+        Action::CopyMagnet(movie) => {
+            let magnet = best_torrent(&movie, None, None)
+                .and_then(|torrent| torrent.magnet(&movie.title_long()))
+                .ok_or_else(|| format!("{} has no torrent", movie.title_long()))?;
+            tui::copy(&magnet)?;
+            Ok(format!("copied the magnet for {}", movie.title_long()))
+        }
+        Action::Open(url) => {
+            tui::open(&url)?;
+            Ok(format!("opened {}", url))
+        }
+        Action::Watchlist(movie) => {
+            let path = watchlist_path_from_clap(matches)?;
+            let mut watchlist = MovieList::load(&path)?;
+            let title = movie.title_long();
+            if !watchlist.add(movie) {
+                return Err(format!("{} has no id to keep track of", title).into());
+            }
+            watchlist.save(&path)?;
+            Ok(format!("added {} to the watchlist", title))
+        }
+        Action::Download(movie) => {
+            let torrent = best_torrent(&movie, None, None)
+                .ok_or_else(|| format!("{} has no torrent", movie.title_long()))?;
+            let url = torrent_url(torrent, mirror_from_clap(matches, config))?;
+            let dir = config.get.dir.clone().unwrap_or_else(|| PathBuf::from("."));
+            let template = config.get.name.as_deref().unwrap_or(DEFAULT_NAME_TEMPLATE);
+            let name = file_name(template, &movie, torrent);
+            let path = download(&url, &dir, &name, false, |path| verify(path, torrent))?;
+            Ok(format!("saved {}", path.display()))
+        }
+    }
+}
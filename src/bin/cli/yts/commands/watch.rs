@@ -0,0 +1,55 @@
+use crate::cli::yts::commands::fail;
+use crate::cli::yts::input::{
+    data_path_from_clap, list_req_from_clap, post_filter_from_clap, sinks_from_clap,
+};
+use clap::ArgMatches;
+use log::warn;
+use std::thread::sleep;
+use std::time::Duration;
+use yts::config::Config;
+use yts::watch::WatchState;
+
+pub fn watch(watch_matches: &ArgMatches, config: &Config) {
+    let request =
+        list_req_from_clap(watch_matches, config).unwrap_or_else(|e| fail("building the query", e));
+    let filter = post_filter_from_clap(watch_matches, config)
+        .unwrap_or_else(|e| fail("building the query", e));
+    let sinks = sinks_from_clap(watch_matches);
+    let state_path = data_path_from_clap(watch_matches, "state", "watch.json")
+        .unwrap_or_else(|e| fail("loading the watch state", e));
+    let mut state =
+        WatchState::load(&state_path).unwrap_or_else(|e| fail("loading the watch state", e));
+    let interval = Duration::from_secs(*watch_matches.get_one::<u64>("interval").unwrap());
+    let once = watch_matches.is_present("once");
+    let mut prime = watch_matches.is_present("prime");
+
+    loop {
+        match request.execute() {
+            Ok(mut list) => {
+                filter.apply(&mut list);
+                let movies = list.data.and_then(|d| d.movies).unwrap_or_default();
+                let changes = state.update(&movies);
+                if !prime {
+                    for change in &changes {
+                        for sink in &sinks {
+                            if let Err(e) = sink.emit(change) {
+                                warn!("can't deliver '{}': {}", change, e);
+                            }
+                        }
+                    }
+                }
+                prime = false;
+                state
+                    .save(&state_path)
+                    .unwrap_or_else(|e| fail("saving the watch state", e));
+            }
+            Err(e) if once => fail("retrieving the list", e),
+            Err(e) => warn!("can't retrieve the list, will try again later: {}", e),
+        }
+
+        if once {
+            break;
+        }
+        sleep(interval);
+    }
+}
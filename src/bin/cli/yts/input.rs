@@ -1,57 +1,368 @@
-use clap::ArgMatches;
+use clap::{ArgMatches, ValueSource};
 use std::error::Error;
-use yts::request::ListRequest;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+use yts::config::{Config, ListDefaults};
+use yts::date::Since;
+use yts::db::Catalogue;
+use yts::filter::PostFilter;
+use yts::imdb::ImdbData;
+use yts::library::Library;
+use yts::lists::MovieList;
+use yts::request::{Genre, ListRequest, MovieRef};
+use yts::size::ByteSize;
+use yts::view::Extras;
+use yts::watch::Sink;
 
-pub fn list_req_from_clap<'a>(matches: &'a ArgMatches) -> Result<ListRequest<'a>, Box<dyn Error>> {
+/// Builds the request out of the command line, the profile named by an `@name` search word
+/// and the `[list]` config defaults, in this order of precedence.
+pub fn list_req_from_clap<'a>(
+    matches: &'a ArgMatches,
+    config: &'a Config,
+) -> Result<ListRequest<'a>, Box<dyn Error>> {
+    let layers = config_layers(matches, config)?;
     let mut r = ListRequest::new();
 
-    if let Some(val) = matches.value_of("limit") {
+    if let Some(val) = given_value(matches, "limit") {
+        r.limit(val.parse()?);
+    } else if let Some(val) = pick(&layers, |l| l.limit) {
+        r.limit(val);
+    } else if let Some(val) = matches.value_of("limit") {
         r.limit(val.parse()?);
     }
-    if let Some(val) = matches.value_of("page") {
-        r.page(val.parse()?);
+    if let Some(val) = matches.get_one::<u16>("page") {
+        r.page((*val).into());
+    } else if let Some(val) = pick(&layers, |l| l.page) {
+        r.page(val);
     }
-    if let Some(val) = matches.value_of("quality") {
-        r.quality(val.parse()?);
+    if let Some(val) = matches
+        .value_of("quality")
+        .or_else(|| pick(&layers, |l| l.quality.as_deref()))
+    {
+        r.quality(
+            val.parse()
+                .map_err(|_| format!("invalid quality '{}'", val))?,
+        );
     }
-    if let Some(val) = matches.value_of("rating") {
-        r.rating(val.parse()?);
+    if let Some(val) = matches.get_one::<u8>("rating") {
+        r.rating(*val);
+    } else if let Some(val) = pick(&layers, |l| l.rating) {
+        r.rating(val);
     }
-    if let Some(vals) = matches.values_of("search") {
-        let query = vals
-            .into_iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>()
-            .join(" ");
-        r.query_term(query);
+    let words = search_words(matches);
+    if !words.is_empty() {
+        r.query_term(words.join(" "));
+    } else if let Some(val) = pick(&layers, |l| l.search.clone()) {
+        r.query_term(val);
     }
-    if let Some(val) = matches.value_of("genre") {
-        r.genre(val);
+    let genre = match matches.get_one::<Genre>("genre") {
+        Some(genre) => Some(*genre),
+        None => pick(&layers, |l| l.genre.as_deref())
+            .map(Genre::lookup)
+            .transpose()?,
+    };
+    if let Some(genre) = genre {
+        r.genre(genre);
     }
-    if let Some(val) = matches.value_of("sort") {
-        r.sort_by(val.parse()?);
+    if let Some(val) = matches
+        .value_of("sort")
+        .or_else(|| pick(&layers, |l| l.sort.as_deref()))
+    {
+        r.sort_by(val.parse().map_err(|_| format!("invalid sort '{}'", val))?);
     }
-    if let Some(val) = matches.value_of("order") {
-        r.order_by(val.parse()?);
+    if let Some(val) = matches
+        .value_of("order")
+        .or_else(|| pick(&layers, |l| l.order.as_deref()))
+    {
+        r.order_by(
+            val.parse()
+                .map_err(|_| format!("invalid order '{}'", val))?,
+        );
     }
-    if let Some(val) = matches.value_of("mirror") {
+    if let Some(val) = given_value(matches, "mirror")
+        .or_else(|| pick(&layers, |l| l.mirror.as_deref()))
+        .or_else(|| mirror_from_clap(matches, config))
+    {
         r.mirror(val)?;
     }
-    r.wirth_rt_ratings(matches.is_present("with_rotten_tomatoes"));
+    r.wirth_rt_ratings(
+        matches.is_present("with_rotten_tomatoes") || pick(&layers, |l| l.rt).unwrap_or(false),
+    );
+    r.cache(&config.cache);
 
     Ok(r)
 }
 
+pub fn post_filter_from_clap(
+    matches: &ArgMatches,
+    config: &Config,
+) -> Result<PostFilter, Box<dyn Error>> {
+    let layers = config_layers(matches, config)?;
+    let mut f = PostFilter::new();
+
+    if let Some(val) = matches
+        .get_one::<f32>("min-rating")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.min_rating))
+    {
+        f.min_rating(val);
+    }
+    if let Some(val) = matches
+        .get_one::<u16>("min-year")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.min_year))
+    {
+        f.min_year(val);
+    }
+    if let Some(val) = matches
+        .get_one::<u16>("max-year")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.max_year))
+    {
+        f.max_year(val);
+    }
+    if let Some(val) = matches
+        .value_of("language")
+        .or_else(|| pick(&layers, |l| l.language.as_deref()))
+    {
+        f.language(val.to_string());
+    }
+    if let Some(val) = matches
+        .get_one::<ByteSize>("min-size")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.min_size))
+    {
+        f.min_size(val);
+    }
+    if let Some(val) = matches
+        .get_one::<ByteSize>("max-size")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.max_size))
+    {
+        f.max_size(val);
+    }
+    if let Some(val) = matches
+        .get_one::<Since>("since")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.since))
+    {
+        f.since(val);
+    }
+    if let Some(val) = matches
+        .get_one::<u32>("min-votes")
+        .copied()
+        .or_else(|| pick(&layers, |l| l.min_votes))
+    {
+        f.min_votes(val);
+    }
+    if let Some(val) = matches
+        .value_of("director")
+        .or_else(|| pick(&layers, |l| l.director.as_deref()))
+    {
+        f.director(val.to_string());
+    }
+    if let Some(val) = matches
+        .value_of("original-title")
+        .or_else(|| pick(&layers, |l| l.original_title.as_deref()))
+    {
+        f.original_title(val.to_string());
+    }
+    if f.needs_imdb() {
+        f.imdb(imdb_from_clap(matches)?);
+    }
+    if matches.is_present("hide-seen") {
+        f.exclude(MovieList::load(&seen_path_from_clap(matches)?)?.ids());
+    }
+    if matches.is_present("hide-owned") {
+        f.exclude(Library::load(&library_path_from_clap(matches)?)?.ids());
+    }
+
+    Ok(f)
+}
+
+/// The IMDb data built by the enrich command.
+fn imdb_from_clap(matches: &ArgMatches) -> Result<Arc<ImdbData>, Box<dyn Error>> {
+    let path = imdb_path_from_clap(matches)?;
+    if !path.exists() {
+        return Err("there's no IMDb data yet, run yts enrich --imdb-datasets <dir> first".into());
+    }
+    let data = ImdbData::load(&path).map_err(|e| format!("reading {}: {}", path.display(), e))?;
+    Ok(Arc::new(data))
+}
+
+/// What --imdb and --mark-seen ask to show next to the movies.
+#[derive(Default)]
+pub struct Shown {
+    imdb: Option<Arc<ImdbData>>,
+    seen: Option<MovieList>,
+}
+
+impl Shown {
+    pub fn extras(&self) -> Extras<'_> {
+        Extras {
+            imdb: self.imdb.as_deref(),
+            seen: self.seen.as_ref(),
+        }
+    }
+}
+
+/// Loads what the list should show, taking the IMDb data from the filter if it has it already.
+pub fn shown_from_clap(matches: &ArgMatches, filter: &PostFilter) -> Result<Shown, Box<dyn Error>> {
+    let mut shown = Shown::default();
+    if matches.is_present("show-imdb") {
+        shown.imdb = Some(match filter.imdb_data() {
+            Some(imdb) => imdb.clone(),
+            None => imdb_from_clap(matches)?,
+        });
+    }
+    if matches.is_present("mark-seen") {
+        shown.seen = Some(MovieList::load(&seen_path_from_clap(matches)?)?);
+    }
+    Ok(shown)
+}
+
+/// The explicitly asked for mirror, or else the preferred one from the config, or else the default.
+pub fn mirror_from_clap<'a>(matches: &'a ArgMatches, config: &'a Config) -> Option<&'a str> {
+    given_value(matches, "mirror")
+        .or_else(|| config.mirror())
+        .or_else(|| matches.value_of("mirror"))
+}
+
+/// Loads the file given by --config, or the one in the user's config directory.
+pub fn config_from_clap(matches: &ArgMatches) -> Result<Config, Box<dyn Error>> {
+    match matches.value_of("config") {
+        Some(path) => Config::load(Path::new(path)),
+        None => match Config::default_path() {
+            Some(path) => Config::load(&path),
+            None => Ok(Config::default()),
+        },
+    }
+}
+
+/// The value of an argument, only if it was actually typed in (as opposed to a default value).
+fn given_value<'a>(matches: &'a ArgMatches, id: &str) -> Option<&'a str> {
+    match matches.value_source(id) {
+        Some(ValueSource::CommandLine) => matches.value_of(id),
+        _ => None,
+    }
+}
+
+/// The search words, without the `@profile` one.
+fn search_words(matches: &ArgMatches) -> Vec<&str> {
+    matches
+        .values_of("search")
+        .map(|vals| vals.filter(|word| !word.starts_with('@')).collect())
+        .unwrap_or_default()
+}
+
+/// The config sections that apply, most specific first.
+fn config_layers<'a>(
+    matches: &ArgMatches,
+    config: &'a Config,
+) -> Result<Vec<&'a ListDefaults>, Box<dyn Error>> {
+    let profiles: Vec<&str> = matches
+        .values_of("search")
+        .map(|vals| vals.filter_map(|word| word.strip_prefix('@')).collect())
+        .unwrap_or_default();
+
+    let mut layers = match profiles.as_slice() {
+        [] => vec![],
+        [name] => vec![config.profile(name)?],
+        _ => return Err("only one @profile can be used at a time".into()),
+    };
+    layers.push(&config.list);
+
+    Ok(layers)
+}
+
+/// The first value any of the layers has.
+fn pick<'a, T>(
+    layers: &[&'a ListDefaults],
+    get: impl Fn(&'a ListDefaults) -> Option<T>,
+) -> Option<T> {
+    layers.iter().find_map(|layer| get(layer))
+}
+
+/// Stdout, plus whatever hooks were given.
+pub fn sinks_from_clap(matches: &ArgMatches) -> Vec<Sink> {
+    let mut sinks = vec![Sink::Stdout];
+
+    if let Some(command) = matches.value_of("notify-cmd") {
+        sinks.push(Sink::Command(command.to_string()));
+    }
+    if let Some(url) = matches.get_one::<Url>("webhook") {
+        sinks.push(Sink::Webhook(url.clone()));
+    }
+
+    sinks
+}
+
+/// A file in the user's data directory (or in --data-dir), unless `arg` says otherwise.
+pub fn data_path_from_clap(
+    matches: &ArgMatches,
+    arg: &str,
+    file_name: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(path) = matches.try_get_one::<String>(arg).ok().flatten() {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = match matches.try_get_one::<String>("data-dir").ok().flatten() {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir()
+            .ok_or("can't find the user's data directory, please pass --data-dir")?
+            .join("yts"),
+    };
+    Ok(dir.join(file_name))
+}
+
+pub fn watchlist_path_from_clap(matches: &ArgMatches) -> Result<PathBuf, Box<dyn Error>> {
+    data_path_from_clap(matches, "watchlist", "watchlist.json")
+}
+
+pub fn imdb_path_from_clap(matches: &ArgMatches) -> Result<PathBuf, Box<dyn Error>> {
+    data_path_from_clap(matches, "imdb", "imdb.json")
+}
+
+pub fn seen_path_from_clap(matches: &ArgMatches) -> Result<PathBuf, Box<dyn Error>> {
+    data_path_from_clap(matches, "seen", "seen.json")
+}
+
+pub fn library_path_from_clap(matches: &ArgMatches) -> Result<PathBuf, Box<dyn Error>> {
+    data_path_from_clap(matches, "library", "library.json")
+}
+
+/// The movies given as arguments, as yts ids or imdb codes.
+pub fn movie_refs_from_clap(matches: &ArgMatches) -> Result<Vec<MovieRef>, Box<dyn Error>> {
+    matches
+        .values_of("movie")
+        .into_iter()
+        .flatten()
+        .map(|movie| movie.parse().map_err(|e: String| e.into()))
+        .collect()
+}
+
+/// Opens the catalogue given by --db, falling back to the one in the user's data directory.
+pub fn catalogue_from_clap(matches: &ArgMatches) -> Result<Catalogue, Box<dyn Error>> {
+    Catalogue::open(&data_path_from_clap(matches, "db", "catalogue.sqlite")?)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cli::yts::app::clap_app;
     use crate::cli::yts::input::list_req_from_clap;
+    use yts::config::Config;
 
     fn test_url(vec: Vec<&str>) -> String {
+        test_url_with_config(vec, "")
+    }
+
+    fn test_url_with_config(vec: Vec<&str>, config: &str) -> String {
+        let config = Config::parse(config).expect("expected a config");
         let actual_vec = &mut vec!["yts", "list"];
         actual_vec.extend(vec);
         let matches = clap_app().get_matches_from(actual_vec.to_vec());
-        let request = list_req_from_clap(matches.subcommand_matches("list").unwrap())
+        let request = list_req_from_clap(matches.subcommand_matches("list").unwrap(), &config)
             .expect("expected a request");
         request.url().to_string()
     }
@@ -103,4 +414,60 @@ mod tests {
             "https://yts.mx/api/v2/list_movies.json?limit=50&quality=720p"
         );
     }
+
+    static CONFIG: &str = r#"
+        mirrors = ["yts.lt"]
+
+        [list]
+        limit = 20
+        quality = "1080p"
+
+        [profile.4k-scifi]
+        quality = "2160p"
+        genre = "sci-fi"
+    "#;
+
+    #[test]
+    fn config_defaults() {
+        assert_eq!(
+            test_url_with_config(vec![], CONFIG),
+            "https://yts.lt/api/v2/list_movies.json?limit=20&quality=1080p"
+        );
+    }
+
+    #[test]
+    fn profile() {
+        assert_eq!(
+            test_url_with_config(vec!["@4k-scifi"], CONFIG),
+            "https://yts.lt/api/v2/list_movies.json?limit=20&quality=2160p&genre=sci-fi"
+        );
+    }
+
+    #[test]
+    fn flags_override_profiles() {
+        assert_eq!(
+            test_url_with_config(
+                vec!["@4k-scifi", "-q", "720p", "-l", "50", "--mirror", "yts.mx", "alien"],
+                CONFIG
+            ),
+            "https://yts.mx/api/v2/list_movies.json?limit=50&quality=720p&query_term=alien&genre=sci-fi"
+        );
+    }
+
+    #[test]
+    fn misspelled_genre() {
+        let config = Config::parse("[list]\ngenre = \"horor\"").unwrap();
+        let matches = clap_app().get_matches_from(vec!["yts", "list"]);
+        let error = list_req_from_clap(matches.subcommand_matches("list").unwrap(), &config)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "unknown genre 'horor', did you mean 'horror'?");
+    }
+
+    #[test]
+    fn unknown_profile() {
+        let config = Config::parse(CONFIG).unwrap();
+        let matches = clap_app().get_matches_from(vec!["yts", "list", "@nope"]);
+        assert!(list_req_from_clap(matches.subcommand_matches("list").unwrap(), &config).is_err());
+    }
 }
@@ -1,2 +1,5 @@
 pub mod app;
+pub mod commands;
 pub mod input;
+pub mod output;
+pub mod tui;
@@ -0,0 +1,85 @@
+use clap::ArgMatches;
+use std::error::Error;
+use strum::{Display, EnumString, EnumVariantNames};
+use yts::config::Config;
+use yts::parse::api::ListResponse;
+use yts::view::{Extras, ListView};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "snake_case")]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+/// --format if given, or else the one from the config, or else a table.
+pub fn format_from_clap(matches: &ArgMatches, config: &Config) -> Result<Format, Box<dyn Error>> {
+    match matches.value_of("format").or(config.format.as_deref()) {
+        Some(format) => Ok(format
+            .parse()
+            .map_err(|_| format!("invalid output format '{}'", format))?),
+        None => Ok(Format::Table),
+    }
+}
+
+pub fn print_list(
+    list: &ListResponse,
+    extras: Extras,
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    let view = ListView::new(list, extras);
+    match format {
+        Format::Table => {
+            println!("{}", view);
+            if !list.movies().is_empty() {
+                println!("{}", list.summary());
+            }
+        }
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&view.movies())?);
+        }
+        Format::Csv => {
+            let mut csv = csv::Writer::from_writer(std::io::stdout());
+            csv.write_record([
+                "id",
+                "imdb_code",
+                "title",
+                "year",
+                "rating",
+                "genres",
+                "language",
+                "torrents",
+                "url",
+                "imdb_votes",
+                "directors",
+                "original_title",
+                "seen",
+            ])?;
+            for view in view.movies() {
+                let movie = view.movie;
+                let imdb = view.imdb_info.flatten();
+                csv.write_record([
+                    movie.id(),
+                    movie.imdb_code.clone().unwrap_or_default(),
+                    movie.title(),
+                    movie.year(),
+                    movie.rating(),
+                    movie.genres(),
+                    movie.language.clone().unwrap_or_default(),
+                    movie.torrent_sizes(),
+                    movie.url(),
+                    imdb.and_then(|i| i.votes)
+                        .map(|votes| votes.to_string())
+                        .unwrap_or_default(),
+                    imdb.map(|i| i.directors.join(", ")).unwrap_or_default(),
+                    imdb.and_then(|i| i.original_title.clone())
+                        .unwrap_or_default(),
+                    if view.seen { "true" } else { "" }.to_string(),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,480 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use yts::parse::api::{ListResponse, Movie, MovieDescription};
+
+/// Gets the given page of the list, for the given search (or for the original query when None).
+pub type Fetch<'a> = Box<dyn FnMut(u32, Option<&str>) -> Result<ListResponse, Box<dyn Error>> + 'a>;
+
+const HELP: &str =
+    "j/k move  / filter  s search  n more  m magnet  t trailer  o page  i imdb  w watchlist  d download  q quit";
+
+/// What the user asked for that goes beyond the screen.
+#[derive(Debug)]
+pub enum Action {
+    CopyMagnet(Movie),
+    Open(String),
+    Watchlist(Movie),
+    Download(Movie),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Mode {
+    Browse,
+    Filter,
+    Search,
+}
+
+pub struct App<'a> {
+    fetch: Fetch<'a>,
+    movies: Vec<Movie>,
+    search: Option<String>,
+    filter: String,
+    input: String,
+    mode: Mode,
+    selected: usize,
+    page: u32,
+    more: bool,
+    status: String,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(fetch: Fetch<'a>) -> App<'a> {
+        App {
+            fetch,
+            movies: vec![],
+            search: None,
+            filter: String::new(),
+            input: String::new(),
+            mode: Mode::Browse,
+            selected: 0,
+            page: 0,
+            more: true,
+            status: HELP.to_string(),
+            quit: false,
+        }
+    }
+
+    /// Starts over from the first page.
+    pub fn reload(&mut self) {
+        self.movies.clear();
+        self.selected = 0;
+        self.page = 0;
+        self.more = true;
+        self.load_more();
+    }
+
+    /// Appends the next page, if there is one.
+    pub fn load_more(&mut self) {
+        if !self.more {
+            return;
+        }
+        match (self.fetch)(self.page + 1, self.search.as_deref()) {
+            Ok(list) => {
+                self.page += 1;
+                let (count, limit, movies) = list
+                    .data
+                    .map(|data| {
+                        (
+                            data.movie_count,
+                            data.limit,
+                            data.movies.unwrap_or_default(),
+                        )
+                    })
+                    .unwrap_or_default();
+                // the movies may have been filtered, so it's the paging that tells what's left
+                self.more = match (count, limit) {
+                    (Some(count), Some(limit)) => count > u64::from(self.page) * u64::from(limit),
+                    _ => {
+                        !movies.is_empty()
+                            && count.is_none_or(|count| {
+                                count > (self.movies.len() + movies.len()) as u64
+                            })
+                    }
+                };
+                self.movies.extend(movies);
+                self.status = format!(
+                    "{} movies loaded{}",
+                    self.movies.len(),
+                    if self.more { "" } else { ", that's all" }
+                );
+            }
+            Err(e) => self.status = format!("can't load the list: {}", e),
+        }
+    }
+
+    /// The movies that match the filter.
+    pub fn visible(&self) -> Vec<&Movie> {
+        let filter = self.filter.to_lowercase();
+        self.movies
+            .iter()
+            .filter(|movie| {
+                filter.is_empty()
+                    || movie.title_long().to_lowercase().contains(&filter)
+                    || movie.genres().contains(&filter)
+            })
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&Movie> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = status;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return None;
+        }
+        match self.mode {
+            Mode::Browse => self.browse(key.code),
+            Mode::Filter | Mode::Search => {
+                self.edit(key.code);
+                None
+            }
+        }
+    }
+
+    fn browse(&mut self, code: KeyCode) -> Option<Action> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.down(),
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+            KeyCode::Char('n') => self.load_more(),
+            KeyCode::Char('/') => {
+                self.mode = Mode::Filter;
+                self.input = self.filter.clone();
+            }
+            KeyCode::Char('s') => {
+                self.mode = Mode::Search;
+                self.input = self.search.clone().unwrap_or_default();
+            }
+            KeyCode::Char('m') => return self.selected().cloned().map(Action::CopyMagnet),
+            KeyCode::Char('t') => return self.link(Movie::youtube, "trailer"),
+            KeyCode::Char('o') => return self.link(Movie::url, "yts page"),
+            KeyCode::Char('i') => return self.link(Movie::imdb, "imdb page"),
+            KeyCode::Char('w') => return self.selected().cloned().map(Action::Watchlist),
+            KeyCode::Char('d') => return self.selected().cloned().map(Action::Download),
+            _ => {}
+        }
+        None
+    }
+
+    /// Moves down, loading the next page when going past the last movie.
+    fn down(&mut self) {
+        if self.selected + 1 < self.visible().len() {
+            self.selected += 1;
+        } else if self.filter.is_empty() && self.more {
+            let before = self.movies.len();
+            self.load_more();
+            if self.movies.len() > before {
+                self.selected += 1;
+            }
+        }
+    }
+
+    fn link(&mut self, link: fn(&Movie) -> String, name: &str) -> Option<Action> {
+        let url = link(self.selected()?);
+        if url.is_empty() {
+            self.status = format!("there's no {} for this movie", name);
+            return None;
+        }
+        Some(Action::Open(url))
+    }
+
+    fn edit(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Esc => {
+                self.mode = Mode::Browse;
+                return;
+            }
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut self.input);
+                let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                if mode == Mode::Search {
+                    self.search = Some(input).filter(|s| !s.trim().is_empty());
+                    self.filter.clear();
+                    self.reload();
+                }
+                return;
+            }
+            _ => {}
+        }
+        if self.mode == Mode::Filter {
+            self.filter = self.input.clone();
+            self.selected = 0;
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [main, bottom] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|movie| ListItem::new(format!("{} {}", movie.title_long(), movie.rating())))
+            .collect();
+        let title = match &self.search {
+            Some(search) => format!(" movies: {} (page {}) ", search, self.page),
+            None => format!(" movies (page {}) ", self.page),
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        state.select((!visible.is_empty()).then_some(self.selected));
+        frame.render_stateful_widget(list, left, &mut state);
+
+        let details = Paragraph::new(self.selected().map(details).unwrap_or_default())
+            .block(Block::default().borders(Borders::ALL).title(" details "))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, right);
+
+        let bottom_line = match self.mode {
+            Mode::Browse => self.status.clone(),
+            Mode::Filter => format!("/{}", self.input),
+            Mode::Search => format!("search: {}", self.input),
+        };
+        frame.render_widget(Paragraph::new(bottom_line), bottom);
+    }
+}
+
+/// The detail pane for a movie.
+fn details(movie: &Movie) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::styled(
+            movie.title_long(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Line::from(format!(
+            "rating {}  runtime {} min  {}  {}",
+            movie.rating(),
+            movie.runtime.unwrap_or_default(),
+            movie.language.as_deref().unwrap_or_default(),
+            movie.genres()
+        )),
+        Line::default(),
+    ];
+    let mut summary = movie.text(MovieDescription::Summary);
+    if summary.is_empty() {
+        summary = movie.text(MovieDescription::Description);
+    }
+    lines.push(Line::from(summary));
+    lines.push(Line::default());
+    for torrent in movie.torrents_by_size() {
+        lines.push(Line::from(format!(
+            "{:<6} {:<7} {:>10}  {} seeds / {} peers",
+            torrent.quality.as_deref().unwrap_or_default(),
+            torrent.ty_pe.as_deref().unwrap_or_default(),
+            torrent.bytes().map(|s| s.to_string()).unwrap_or_default(),
+            torrent.seeders.unwrap_or_default(),
+            torrent.peers.unwrap_or_default()
+        )));
+    }
+    lines.push(Line::default());
+    for link in [movie.url(), movie.imdb(), movie.youtube()] {
+        if !link.is_empty() {
+            lines.push(Line::from(link));
+        }
+    }
+    lines
+}
+
+/// Runs the interface until the user quits, handing the actions to `perform`, whose answer
+/// ends up in the status line.
+pub fn run(
+    app: &mut App,
+    perform: &mut dyn FnMut(Action) -> Result<String, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, app, perform);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    perform: &mut dyn FnMut(Action) -> Result<String, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    app.reload();
+    while !app.quit() {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(action) = app.handle_key(key) {
+                let status = perform(action).unwrap_or_else(|e| e.to_string());
+                app.set_status(status);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Puts the text on the clipboard through the terminal (OSC 52), which works over ssh as well.
+pub fn copy(text: &str) -> Result<(), Box<dyn Error>> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64::encode(text))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Opens the url with the desktop's default handler.
+pub fn open(url: &str) -> Result<(), Box<dyn Error>> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::yts::tui::{Action, App};
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+    use ratatui::Terminal;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use yts::parse::api::ListResponse;
+    static JSON: &str = include_str!("../../../parse/test-data/list.json");
+
+    type Calls = Rc<RefCell<Vec<(u32, Option<String>)>>>;
+
+    /// An app that gets the fixture for the first page and nothing afterwards, recording the calls.
+    fn app(calls: Calls) -> App<'static> {
+        let mut app = App::new(Box::new(move |page, search| {
+            calls
+                .borrow_mut()
+                .push((page, search.map(|s| s.to_string())));
+            if page == 1 {
+                Ok(serde_json::from_str::<ListResponse>(JSON)?)
+            } else {
+                Ok(Vec::new().into())
+            }
+        }));
+        app.reload();
+        app
+    }
+
+    fn press(app: &mut App, keys: &str) -> Option<Action> {
+        let mut action = None;
+        for c in keys.chars() {
+            action = app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+        action
+    }
+
+    #[test]
+    fn pages_on_demand() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut app = app(calls.clone());
+        let count = app.visible().len();
+        assert!(count > 1);
+
+        press(&mut app, &"j".repeat(count));
+        assert_eq!(app.selected().unwrap().id, app.visible().last().unwrap().id);
+        assert_eq!(*calls.borrow(), vec![(1, None), (2, None)]);
+
+        press(&mut app, "jjn");
+        assert_eq!(calls.borrow().len(), 2);
+    }
+
+    #[test]
+    fn pages_past_filtered_pages() {
+        let mut app = App::new(Box::new(|_, _| {
+            // a page whose movies were all filtered out
+            let mut list = serde_json::from_str::<ListResponse>(JSON)?;
+            list.data.as_mut().unwrap().movies = Some(vec![]);
+            Ok(list)
+        }));
+        app.reload();
+        assert!(app.visible().is_empty());
+        assert!(app.more);
+        app.load_more();
+        assert_eq!(app.page, 2);
+    }
+
+    #[test]
+    fn filters_and_searches() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut app = app(calls.clone());
+
+        press(&mut app, "/babbuini");
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.visible().len(), 1);
+        assert_eq!(app.selected().unwrap().id, Some(32080));
+
+        press(&mut app, "salien");
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(calls.borrow().last(), Some(&(1, Some("alien".to_string()))));
+        assert!(app.visible().len() > 1);
+    }
+
+    #[test]
+    fn actions() {
+        let mut app = app(Rc::new(RefCell::new(vec![])));
+        assert!(matches!(press(&mut app, "m"), Some(Action::CopyMagnet(m)) if m.id == Some(32080)));
+        assert!(matches!(press(&mut app, "w"), Some(Action::Watchlist(_))));
+        assert!(matches!(press(&mut app, "d"), Some(Action::Download(_))));
+        assert!(
+            matches!(press(&mut app, "o"), Some(Action::Open(url)) if url.starts_with("https://yts.mx/movies/"))
+        );
+        assert!(!app.quit());
+        press(&mut app, "q");
+        assert!(app.quit());
+    }
+
+    #[test]
+    fn draws() {
+        let app = app(Rc::new(RefCell::new(vec![])));
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("La via dei babbuini (1974)"));
+        assert!(screen.contains("720p"));
+        assert!(screen.contains("movies (page 1)"));
+    }
+}
@@ -1,4 +1,12 @@
 use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+use serde::Deserialize;
+use std::error::Error;
+use yts::artwork::{content_type, ArtCache, ArtKind, ArtSize};
+use yts::client;
+use yts::config::Config;
+use yts::nfo::movie_nfo;
+use yts::parse::api::Movie;
+use yts::request::{DetailsRequest, MovieRef};
 // use crate::cli::http::app::clap_app;
 
 #[get("/")]
@@ -11,14 +19,134 @@ async fn healthcheck() -> impl Responder {
     HttpResponse::Ok().body("I'm alive!")
 }
 
+/// The movie's details, asked for with the cache and mirror settings of the config.
+fn details(movie: MovieRef, config: &Config) -> Result<Option<Movie>, Box<dyn Error>> {
+    let mut request = DetailsRequest::new(movie);
+    request.cache(&config.cache);
+    if let Some(mirror) = config.mirror() {
+        request.mirror(mirror)?;
+    }
+    request.execute()
+}
+
+/// Kodi style .nfo metadata for a movie, by yts id or imdb code.
+#[get("/movies/{movie}/nfo")]
+async fn nfo(movie: web::Path<String>, config: web::Data<Config>) -> impl Responder {
+    let movie: MovieRef = match movie.parse() {
+        Ok(movie) => movie,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let details = web::block(move || details(movie, &config).map_err(|e| e.to_string())).await;
+
+    match details {
+        Ok(Ok(Some(movie))) => HttpResponse::Ok()
+            .content_type("application/xml; charset=utf-8")
+            .body(movie_nfo(&movie)),
+        Ok(Ok(None)) => HttpResponse::NotFound().body("no such movie"),
+        Ok(Err(e)) => HttpResponse::BadGateway().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// An image from the artwork cache, by its content addressed name.
+#[get("/art/{name}")]
+async fn art(name: web::Path<String>, cache: web::Data<ArtCache>) -> impl Responder {
+    let path = match cache.get(&name) {
+        Some(path) => path,
+        None => return HttpResponse::NotFound().body("no such image"),
+    };
+    match web::block(move || std::fs::read(&path).map(|bytes| (content_type(&path), bytes))).await {
+        Ok(Ok((content_type, bytes))) => HttpResponse::Ok()
+            .content_type(content_type)
+            // the name changes whenever the content does
+            .insert_header(("cache-control", "public, max-age=31536000, immutable"))
+            .body(bytes),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ArtQuery {
+    size: Option<ArtSize>,
+}
+
+/// Caches a movie's artwork if it isn't already and redirects to it.
+#[get("/movies/{movie}/art/{kind}")]
+async fn movie_art(
+    path: web::Path<(String, String)>,
+    query: web::Query<ArtQuery>,
+    cache: web::Data<ArtCache>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let (movie, kind) = path.into_inner();
+    let movie: MovieRef = match movie.parse() {
+        Ok(movie) => movie,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let kind: ArtKind = match kind.parse() {
+        Ok(kind) => kind,
+        Err(_) => return HttpResponse::NotFound().body("no such kind of artwork"),
+    };
+    let size = query.size.unwrap_or(ArtSize::Large);
+
+    let cached = web::block(move || {
+        let movie = match details(movie, &config) {
+            Ok(Some(movie)) => movie,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        match movie.artwork(kind, size) {
+            Some(url) => cache.fetch(url).map(Some).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    })
+    .await;
+
+    match cached {
+        Ok(Ok(Some(path))) => {
+            let name = path.file_name().unwrap().to_string_lossy();
+            HttpResponse::Found()
+                .insert_header(("location", format!("/art/{}", name)))
+                .finish()
+        }
+        Ok(Ok(None)) => HttpResponse::NotFound().body("no such movie or artwork"),
+        Ok(Err(e)) => HttpResponse::BadGateway().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 pub fn init(config: &mut web::ServiceConfig) {
-    config.service(web::scope("").service(index).service(healthcheck));
+    config.service(
+        web::scope("")
+            .service(index)
+            .service(healthcheck)
+            .service(nfo)
+            .service(art)
+            .service(movie_art),
+    );
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    HttpServer::new(|| App::new().configure(init))
-        .bind("0.0.0.0:8001")?
-        .run()
-        .await
+fn main() -> std::io::Result<()> {
+    let config = match Config::default_path() {
+        Some(path) => Config::load(&path).map_err(|e| std::io::Error::other(e.to_string()))?,
+        None => Config::default(),
+    };
+    // the blocking http client can't be built from within the async runtime
+    client::configure(&config.http).map_err(|e| std::io::Error::other(e.to_string()))?;
+    let cache = web::Data::new(
+        ArtCache::from_settings(&config.art).map_err(|e| std::io::Error::other(e.to_string()))?,
+    );
+    let config = web::Data::new(config);
+
+    actix_web::rt::System::new().block_on(
+        HttpServer::new(move || {
+            App::new()
+                .app_data(cache.clone())
+                .app_data(config.clone())
+                .configure(init)
+        })
+        .bind(std::env::var("YTS_HTTP_BIND").unwrap_or_else(|_| "0.0.0.0:8001".to_string()))?
+        .run(),
+    )
 }
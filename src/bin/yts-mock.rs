@@ -0,0 +1,164 @@
+use actix_web::http::StatusCode;
+use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer};
+use clap::{Arg, ArgMatches};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use url::Url;
+use yts::mock::MockApi;
+
+/// Latency and errors to inject, for all requests or, through the `mock_latency` (milliseconds)
+/// and `mock_status` query parameters, for single ones.
+struct Faults {
+    latency: Duration,
+    fail_every: Option<u64>,
+    requests: AtomicU64,
+}
+
+type Query = web::Query<Vec<(String, String)>>;
+
+impl Faults {
+    /// The response to give instead of the real one, if any, after waiting for as long as asked.
+    async fn inject(&self, query: &Query) -> Option<HttpResponse> {
+        let param = |name: &str| {
+            query
+                .iter()
+                .find(|(key, _)| key == name)
+                .and_then(|(_, value)| value.parse::<u64>().ok())
+        };
+
+        let latency = param("mock_latency")
+            .map(Duration::from_millis)
+            .unwrap_or(self.latency);
+        if !latency.is_zero() {
+            actix_web::rt::time::sleep(latency).await;
+        }
+
+        let count = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        let status = param("mock_status")
+            .and_then(|status| StatusCode::from_u16(status as u16).ok())
+            .or_else(|| {
+                self.fail_every
+                    .filter(|every| count.is_multiple_of(*every))
+                    .map(|_| StatusCode::INTERNAL_SERVER_ERROR)
+            })?;
+        Some(HttpResponse::build(status).body(format!("mock {}", status)))
+    }
+}
+
+fn respond<T: Serialize>(response: Result<T, String>) -> HttpResponse {
+    match response {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "status_message": message,
+        })),
+    }
+}
+
+#[get("/api/v2/list_movies.json")]
+async fn list(query: Query, api: web::Data<MockApi>, faults: web::Data<Faults>) -> HttpResponse {
+    match faults.inject(&query).await {
+        Some(response) => response,
+        None => respond(api.list(&query)),
+    }
+}
+
+#[get("/api/v2/movie_details.json")]
+async fn details(query: Query, api: web::Data<MockApi>, faults: web::Data<Faults>) -> HttpResponse {
+    match faults.inject(&query).await {
+        Some(response) => response,
+        None => respond(api.details(&query)),
+    }
+}
+
+#[get("/api/v2/movie_suggestions.json")]
+async fn suggestions(
+    query: Query,
+    api: web::Data<MockApi>,
+    faults: web::Data<Faults>,
+) -> HttpResponse {
+    match faults.inject(&query).await {
+        Some(response) => response,
+        None => respond(api.suggestions(&query)),
+    }
+}
+
+#[get("/torrent/download/{hash}")]
+async fn torrent(
+    request: HttpRequest,
+    hash: web::Path<String>,
+    api: web::Data<MockApi>,
+    faults: web::Data<Faults>,
+) -> HttpResponse {
+    let query = Query::from_query(request.query_string()).unwrap_or_else(|_| web::Query(vec![]));
+    if let Some(response) = faults.inject(&query).await {
+        return response;
+    }
+    match api.torrent(&hash) {
+        Some(bytes) => HttpResponse::Ok()
+            .content_type("application/x-bittorrent")
+            .body(bytes.to_vec()),
+        None => HttpResponse::NotFound().body("no such torrent"),
+    }
+}
+
+fn clap_app() -> clap::Command<'static> {
+    clap::Command::new("yts-mock")
+        .about("serves a set of movies the way the yts api does")
+        .args(&[
+            Arg::new("dir").takes_value(true).required(true)
+                .help("directory of movie json files")
+                .long_help("Directory of .json files, each holding a movie or a list of movies as the api returns them.\n\
+                .torrent files are served from its torrents/ subdirectory, named by their info-hash"),
+            Arg::new("bind").long("bind").takes_value(true).default_value("127.0.0.1:8002")
+                .help("address to listen on"),
+            Arg::new("url").long("url").takes_value(true)
+                .help("the server's address as clients see it, defaults to http://<bind>/")
+                .value_parser(clap::value_parser!(Url)),
+            Arg::new("latency").long("latency").takes_value(true).default_value("0")
+                .help("milliseconds to wait before every response")
+                .value_parser(clap::value_parser!(u64)),
+            Arg::new("fail-every").long("fail-every").takes_value(true)
+                .help("answer every nth request with a 500")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        ])
+}
+
+fn load(matches: &ArgMatches) -> Result<(String, MockApi), Box<dyn std::error::Error>> {
+    let bind = matches.value_of("bind").unwrap().to_string();
+    let base = match matches.get_one::<Url>("url") {
+        Some(url) => url.clone(),
+        None => Url::parse(&format!("http://{}/", bind))?,
+    };
+    let api = MockApi::load(Path::new(matches.value_of("dir").unwrap()), &base)?;
+    Ok((bind, api))
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::init();
+
+    let matches = clap_app().get_matches();
+    let (bind, api) = load(&matches).map_err(|e| std::io::Error::other(e.to_string()))?;
+    let api = web::Data::new(api);
+    let faults = web::Data::new(Faults {
+        latency: Duration::from_millis(*matches.get_one::<u64>("latency").unwrap()),
+        fail_every: matches.get_one::<u64>("fail-every").copied(),
+        requests: AtomicU64::new(0),
+    });
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(api.clone())
+            .app_data(faults.clone())
+            .service(list)
+            .service(details)
+            .service(suggestions)
+            .service(torrent)
+    })
+    .bind(bind)?
+    .run()
+    .await
+}
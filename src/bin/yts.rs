@@ -1,35 +1,79 @@
 mod cli;
 
-use crate::cli::yts::{app::clap_app, input::list_req_from_clap};
-use log::error;
-use std::process::exit;
-use yts::parse::api::ListResponse;
+use crate::cli::yts::{
+    app::clap_app,
+    commands::{
+        db::{db_query, sync},
+        doctor::doctor_schema,
+        fail,
+        imdb::enrich,
+        import::import,
+        info::{completions, genres, man},
+        library::library_scan,
+        list::{list, search},
+        lists::{seen, watchlist},
+        lookup::lookup_file,
+        metadata::{art, nfo},
+        snapshot::{diff, snapshot_save},
+        stats::stats,
+        torrents::{get, inspect, send},
+        tui::tui,
+        watch::watch,
+    },
+    input::config_from_clap,
+};
+use yts::client;
+use yts::parse::schema;
 
 fn main() {
     env_logger::init();
 
     let matches = clap_app().get_matches();
+    let config = config_from_clap(&matches).unwrap_or_else(|e| fail("reading the config", e));
+    client::configure(&config.http).unwrap_or_else(|e| fail("setting up the http client", e));
+    schema::set_strict(config.strict || matches.is_present("strict"));
 
-    // the list subcommand
-    if let Some(list_matches) = matches.subcommand_matches("list") {
-        let list: ListResponse = if list_matches.is_present("response-mock-file") {
-            let json =
-                std::fs::read_to_string(list_matches.value_of("response-mock-file").unwrap())
-                    .expect("can't read mock json data");
-            serde_json::from_str(json.as_str()).expect("expected a parsed response")
-        } else {
-            let request = list_req_from_clap(list_matches).unwrap_or_else(|e| {
-                eprintln!("encountered a problem while retrieving the list");
-                error!("can't retrieve list: {}", e);
-                exit(1);
-            });
-            request.execute().unwrap_or_else(|e| {
-                eprintln!("encountered a problem while retrieving the list");
-                error!("can't retrieve list: {}", e);
-                exit(1);
-            })
-        };
-
-        println!("{}", list);
+    match matches.subcommand() {
+        Some(("list", list_matches)) => list(list_matches, &config),
+        Some(("tui", tui_matches)) => tui(tui_matches, &config),
+        Some(("sync", sync_matches)) => sync(sync_matches, &config),
+        Some(("watch", watch_matches)) => watch(watch_matches, &config),
+        Some(("search", search_matches)) => search(search_matches, &config),
+        Some(("watchlist", watchlist_matches)) => watchlist(watchlist_matches, &config),
+        Some(("seen", seen_matches)) => seen(seen_matches, &config),
+        Some(("library", library_matches)) => {
+            if let Some(("scan", scan_matches)) = library_matches.subcommand() {
+                library_scan(scan_matches)
+            }
+        }
+        Some(("get", get_matches)) => get(get_matches, &config),
+        Some(("send", send_matches)) => send(send_matches, &config),
+        Some(("inspect", inspect_matches)) => inspect(inspect_matches, &config),
+        Some(("nfo", nfo_matches)) => nfo(nfo_matches, &config),
+        Some(("art", art_matches)) => art(art_matches, &config),
+        Some(("genres", genres_matches)) => genres(genres_matches),
+        Some(("completions", completions_matches)) => completions(completions_matches),
+        Some(("man", _)) => man(),
+        Some(("snapshot", snapshot_matches)) => {
+            if let Some(("save", save_matches)) = snapshot_matches.subcommand() {
+                snapshot_save(save_matches, &config)
+            }
+        }
+        Some(("diff", diff_matches)) => diff(diff_matches, &config),
+        Some(("stats", stats_matches)) => stats(stats_matches, &config),
+        Some(("enrich", enrich_matches)) => enrich(enrich_matches),
+        Some(("import", import_matches)) => import(import_matches, &config),
+        Some(("lookup", lookup_matches)) => lookup_file(lookup_matches, &config),
+        Some(("doctor", doctor_matches)) => {
+            if let Some(("schema", schema_matches)) = doctor_matches.subcommand() {
+                doctor_schema(schema_matches, &config)
+            }
+        }
+        Some(("db", db_matches)) => {
+            if let Some(("query", query_matches)) = db_matches.subcommand() {
+                db_query(query_matches, &config)
+            }
+        }
+        _ => {}
     }
 }
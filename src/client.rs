@@ -0,0 +1,312 @@
+use crate::request::client_builder;
+use chrono::{DateTime, Utc};
+use log::info;
+use reqwest::blocking::{Client as HttpClient, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// How requests to yts are retried and throttled.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSettings {
+    /// Attempts per request, the first one included.
+    pub attempts: u32,
+    /// Milliseconds before the first retry. Every retry waits about twice as long as the last one.
+    pub backoff: u64,
+    /// The longest wait between two attempts, in milliseconds, Retry-After included.
+    pub max_backoff: u64,
+    /// Seconds before a request times out.
+    pub timeout: u64,
+    /// Requests per second, for the whole process. 0 turns the limit off.
+    pub rate: f64,
+    /// How many requests can go out back to back before the rate kicks in.
+    pub burst: u32,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            attempts: 4,
+            backoff: 500,
+            max_backoff: 30_000,
+            timeout: 5,
+            rate: 2.0,
+            burst: 5,
+        }
+    }
+}
+
+/// A token bucket: `burst` tokens, refilled at `rate` per second.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, burst: u32) -> Bucket {
+        let burst = f64::from(burst.max(1));
+        Bucket {
+            rate,
+            burst,
+            tokens: burst,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Takes a token and returns how long to wait before using it. Tokens can be taken ahead of
+    /// time, so whoever asks first goes first.
+    fn take(&mut self, now: Instant) -> Duration {
+        if self.rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// The http client for the yts api and its downloads. GET requests wait for the rate limiter
+/// and are retried on timeouts, connection errors, 429s and 5xx responses.
+#[derive(Debug)]
+pub struct Client {
+    settings: HttpSettings,
+    http: HttpClient,
+    bucket: Mutex<Bucket>,
+}
+
+static SHARED: RwLock<Option<Arc<Client>>> = RwLock::new(None);
+
+/// Replaces the client every request of this process goes through.
+pub fn configure(settings: &HttpSettings) -> Result<(), Box<dyn Error>> {
+    let client = Arc::new(Client::new(settings.clone())?);
+    *SHARED.write().map_err(|e| e.to_string())? = Some(client);
+    Ok(())
+}
+
+/// The client every request of this process goes through, with the default settings unless
+/// [`configure`] said otherwise.
+pub fn shared() -> Result<Arc<Client>, Box<dyn Error>> {
+    if let Some(client) = SHARED.read().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(client.clone());
+    }
+    let mut shared = SHARED.write().map_err(|e| e.to_string())?;
+    if shared.is_none() {
+        *shared = Some(Arc::new(Client::new(HttpSettings::default())?));
+    }
+    Ok(shared.as_ref().unwrap().clone())
+}
+
+impl Client {
+    pub fn new(settings: HttpSettings) -> Result<Client, Box<dyn Error>> {
+        let http = client_builder()
+            .timeout(Duration::from_secs(settings.timeout))
+            .build()?;
+        let bucket = Mutex::new(Bucket::new(settings.rate, settings.burst));
+        Ok(Client {
+            settings,
+            http,
+            bucket,
+        })
+    }
+
+    /// Gets the url, retrying as configured. The last response is returned whatever its status.
+    pub fn get(&self, url: &Url, headers: HeaderMap) -> Result<Response, Box<dyn Error>> {
+        let attempts = self.settings.attempts.max(1);
+        for attempt in 1.. {
+            self.throttle()?;
+            let last = attempt >= attempts;
+            let wait = match self.http.get(url.clone()).headers(headers.clone()).send() {
+                Ok(response) if !last && retryable(response.status()) => {
+                    let wait = retry_after(response.headers(), Utc::now())
+                        .unwrap_or_else(|| self.backoff(attempt));
+                    info!("{} answered {}", url, response.status());
+                    wait
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if !last && (e.is_timeout() || e.is_connect()) => {
+                    info!("{} failed: {}", url, e);
+                    self.backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let wait = wait.min(Duration::from_millis(self.settings.max_backoff));
+            info!("retrying {} in {:?}", url, wait);
+            sleep(wait);
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    /// Waits for the rate limiter to let a request through.
+    fn throttle(&self) -> Result<(), Box<dyn Error>> {
+        let wait = self
+            .bucket
+            .lock()
+            .map_err(|e| e.to_string())?
+            .take(Instant::now());
+        if !wait.is_zero() {
+            sleep(wait);
+        }
+        Ok(())
+    }
+
+    /// Half of the exponential delay for the attempt, plus a random part of the other half,
+    /// so that clients that failed together don't retry together.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .settings
+            .backoff
+            .saturating_mul(1 << (attempt - 1).min(20))
+            .min(self.settings.max_backoff);
+        let jitter = RandomState::new().build_hasher().finish() % (delay / 2 + 1);
+        Duration::from_millis(delay / 2 + jitter)
+    }
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || matches!(status.as_u16(), 500 | 502 | 503 | 504)
+}
+
+/// The wait asked for by a Retry-After header, given either in seconds or as a date.
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - now).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{retry_after, Bucket, Client, HttpSettings};
+    use chrono::{TimeZone, Utc};
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use std::time::{Duration, Instant};
+    use url::Url;
+
+    fn client(attempts: u32) -> Client {
+        Client::new(HttpSettings {
+            attempts,
+            backoff: 1,
+            max_backoff: 50,
+            rate: 0.0,
+            ..HttpSettings::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn bucket() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2.0, 2);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::from_millis(500));
+        assert_eq!(bucket.take(start), Duration::from_millis(1000));
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(later), Duration::ZERO);
+
+        let mut unlimited = Bucket::new(0.0, 1);
+        assert_eq!(unlimited.take(start), Duration::ZERO);
+        assert_eq!(unlimited.take(start), Duration::ZERO);
+    }
+
+    #[test]
+    fn reads_retry_after() {
+        let now = Utc.ymd(2015, 10, 21).and_hms(7, 28, 0);
+        let headers = |value| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+            headers
+        };
+        assert_eq!(
+            retry_after(&headers("3"), now),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:10 GMT"), now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:27:00 GMT"), now),
+            None
+        );
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn backs_off() {
+        let client = Client::new(HttpSettings {
+            backoff: 100,
+            max_backoff: 300,
+            ..HttpSettings::default()
+        })
+        .unwrap();
+        for _ in 0..20 {
+            let first = client.backoff(1).as_millis();
+            assert!((50..=100).contains(&first));
+            let third = client.backoff(3).as_millis();
+            assert!((150..=300).contains(&third));
+        }
+    }
+
+    #[test]
+    fn retries_retryable_failures() {
+        let mut server = mockito::Server::new();
+        let url = Url::parse(&format!("{}/list", server.url())).unwrap();
+        let busy = server
+            .mock("GET", "/list")
+            .with_status(503)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create();
+        let ok = server.mock("GET", "/list").with_body("{}").create();
+
+        let response = client(3).get(&url, HeaderMap::new()).unwrap();
+        assert_eq!(response.status(), 200);
+        busy.assert();
+        ok.assert();
+    }
+
+    #[test]
+    fn gives_up() {
+        let mut server = mockito::Server::new();
+        let url = Url::parse(&format!("{}/list", server.url())).unwrap();
+        let limited = server
+            .mock("GET", "/list")
+            .with_status(429)
+            .expect(2)
+            .create();
+        let response = client(2).get(&url, HeaderMap::new()).unwrap();
+        assert_eq!(response.status(), 429);
+        limited.assert();
+
+        let missing = server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .expect(1)
+            .create();
+        let url = url.join("/missing").unwrap();
+        assert_eq!(client(3).get(&url, HeaderMap::new()).unwrap().status(), 404);
+        missing.assert();
+    }
+}
@@ -0,0 +1,231 @@
+use crate::artwork::ArtSettings;
+use crate::client::HttpSettings;
+use crate::date::Since;
+use crate::request::CacheSettings;
+use crate::size::ByteSize;
+use crate::torrent_client::ClientSettings;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The user's settings, read from `config.toml` in the user's config directory
+/// (`$XDG_CONFIG_HOME/yts/config.toml` on linux).
+///
+/// ```toml
+/// mirrors = ["yts.lt", "yts.mx"]
+/// format = "table"
+/// strict = false
+///
+/// [cache]
+/// dir = "/var/cache/yts"
+///
+/// [http]
+/// attempts = 5
+/// rate = 1.0
+///
+/// [list]
+/// limit = 20
+/// quality = "1080p"
+///
+/// [get]
+/// dir = "/srv/torrents"
+/// name = "{title} ({year}) {quality} {type}.torrent"
+///
+/// [clients.qbit]
+/// kind = "qbittorrent"
+/// url = "http://seedbox:8080"
+///
+/// [profile.4k-scifi]
+/// quality = "2160p"
+/// genre = "sci-fi"
+/// min_rating = 7.0
+/// ```
+///
+/// Values given on the command line win over the ones from a profile,
+/// which in turn win over the `[list]` defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Preferred mirrors, the first one is used unless another one is asked for.
+    pub mirrors: Vec<String>,
+    /// Default output format.
+    pub format: Option<String>,
+    /// Fail on api responses that don't fit the expected schema, like `--strict`.
+    pub strict: bool,
+    pub cache: CacheSettings,
+    /// Retries and rate limiting for everything that goes to yts.
+    pub http: HttpSettings,
+    /// Where downloaded artwork is kept.
+    pub art: ArtSettings,
+    /// Defaults for the arguments of the list command.
+    pub list: ListDefaults,
+    /// Defaults for the get command.
+    pub get: GetDefaults,
+    /// Torrent clients things can be sent to, by name.
+    pub clients: BTreeMap<String, ClientSettings>,
+    /// Named sets of list arguments, used as `yts list @name`.
+    pub profile: BTreeMap<String, ListDefaults>,
+}
+
+/// Values for the arguments of the list command. Everything is optional.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListDefaults {
+    pub search: Option<String>,
+    pub limit: Option<u8>,
+    pub page: Option<u32>,
+    pub quality: Option<String>,
+    pub rating: Option<u8>,
+    pub genre: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub rt: Option<bool>,
+    pub mirror: Option<String>,
+    pub min_rating: Option<f32>,
+    pub min_year: Option<u16>,
+    pub max_year: Option<u16>,
+    pub language: Option<String>,
+    pub min_size: Option<ByteSize>,
+    pub max_size: Option<ByteSize>,
+    pub since: Option<Since>,
+    pub min_votes: Option<u32>,
+    pub director: Option<String>,
+    pub original_title: Option<String>,
+}
+
+/// Where and how the get command saves .torrent files.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GetDefaults {
+    pub dir: Option<PathBuf>,
+    /// File name template, see [crate::download::file_name].
+    pub name: Option<String>,
+}
+
+impl Config {
+    /// Where the config file is expected to be.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("yts").join("config.toml"))
+    }
+
+    /// Reads the config at `path`. A missing file means the default config.
+    pub fn load(path: &Path) -> Result<Config, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let toml = std::fs::read_to_string(path)?;
+        Config::parse(&toml).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn parse(toml: &str) -> Result<Config, Box<dyn Error>> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// The profile with the given name.
+    pub fn profile(&self, name: &str) -> Result<&ListDefaults, Box<dyn Error>> {
+        self.profile.get(name).ok_or_else(|| {
+            let known = self.profile.keys().cloned().collect::<Vec<String>>();
+            if known.is_empty() {
+                format!(
+                    "unknown profile '{}', there are no profiles configured",
+                    name
+                )
+                .into()
+            } else {
+                format!(
+                    "unknown profile '{}', try one of: {}",
+                    name,
+                    known.join(", ")
+                )
+                .into()
+            }
+        })
+    }
+
+    /// The client with the given name, or the only one there is when no name is given.
+    pub fn client(&self, name: Option<&str>) -> Result<&ClientSettings, Box<dyn Error>> {
+        let known = || {
+            self.clients
+                .keys()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match name {
+            Some(name) => self.clients.get(name).ok_or_else(|| {
+                format!("unknown client '{}', try one of: {}", name, known()).into()
+            }),
+            None if self.clients.len() == 1 => Ok(self.clients.values().next().unwrap()),
+            None if self.clients.is_empty() => Err("there are no clients configured".into()),
+            None => Err(format!("there are several clients, pick one of: {}", known()).into()),
+        }
+    }
+
+    /// The mirror to use when none is asked for.
+    pub fn mirror(&self) -> Option<&str> {
+        self.mirrors.first().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+
+    #[test]
+    fn parses() {
+        let config = Config::parse(
+            r#"
+            mirrors = ["yts.lt", "yts.mx"]
+            format = "json"
+
+            [cache]
+            enabled = false
+
+            [list]
+            limit = 20
+            quality = "1080p"
+
+            [profile.4k-scifi]
+            quality = "2160p"
+            genre = "sci-fi"
+            min_rating = 7.5
+
+            [get]
+            dir = "/tmp/torrents"
+
+            [clients.qbit]
+            kind = "qbittorrent"
+            url = "http://seedbox:8080"
+            category = "movies"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.mirror(), Some("yts.lt"));
+        assert_eq!(config.format.as_deref(), Some("json"));
+        assert!(!config.cache.enabled);
+        assert_eq!(config.list.limit, Some(20));
+        assert_eq!(config.get.dir, Some("/tmp/torrents".into()));
+        let profile = config.profile("4k-scifi").unwrap();
+        assert_eq!(profile.quality.as_deref(), Some("2160p"));
+        assert_eq!(profile.min_rating, Some(7.5));
+        assert!(config.profile("nope").is_err());
+        let client = config.client(None).unwrap();
+        assert_eq!(client.url.as_str(), "http://seedbox:8080/");
+        assert_eq!(client.options().category.as_deref(), Some("movies"));
+        assert!(config.client(Some("transmission")).is_err());
+    }
+
+    #[test]
+    fn rejects_typos() {
+        assert!(Config::parse("[list]\nqualty = \"720p\"").is_err());
+    }
+
+    #[test]
+    fn empty() {
+        let config = Config::parse("").unwrap();
+        assert!(config.cache.enabled);
+        assert!(config.profile.is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, MIN_DATETIME};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The format of the `date_uploaded` strings, in the server's timezone.
+const UPLOADED_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Reads a `date_uploaded` string like `2021-05-26 15:39:26` in the timezone the api gave in
+/// `@meta.server_timezone`. Timezones it doesn't know about are taken for UTC.
+///
+/// Known timezones are UTC/GMT, offsets like `+02:00` and the european CET, EET and WET, which
+/// the server keeps with summer time.
+pub fn parse_uploaded(date: &str, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+    let local = NaiveDateTime::parse_from_str(date.trim(), UPLOADED_FORMAT).ok()?;
+    Some(to_utc(local, timezone.unwrap_or("UTC")))
+}
+
+fn to_utc(local: NaiveDateTime, timezone: &str) -> DateTime<Utc> {
+    let hours = match timezone.trim().to_ascii_uppercase().as_str() {
+        "WET" => Some(0),
+        "CET" => Some(1),
+        "EET" => Some(2),
+        _ => None,
+    };
+    let standard = match hours {
+        Some(hours) => Duration::hours(hours),
+        None => return Utc.from_utc_datetime(&(local - fixed_offset(timezone))),
+    };
+
+    // eu summer time starts on the last sunday of march and ends on the last sunday of october,
+    // at 01:00 UTC in both cases
+    let utc_if_summer = local - standard - Duration::hours(1);
+    let year = utc_if_summer.year();
+    if (last_sunday(year, 3)..last_sunday(year, 10)).contains(&utc_if_summer) {
+        Utc.from_utc_datetime(&utc_if_summer)
+    } else {
+        Utc.from_utc_datetime(&(local - standard))
+    }
+}
+
+/// Offsets like `+02:00`, `-0500` or `UTC+2`, or else none at all.
+fn fixed_offset(timezone: &str) -> Duration {
+    let offset = timezone
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let sign = match offset.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Duration::zero(),
+    };
+    let digits: String = offset[1..].chars().filter(char::is_ascii_digit).collect();
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse().unwrap_or(0), 0),
+        3 | 4 => {
+            let (hours, minutes) = digits.split_at(digits.len() - 2);
+            (hours.parse().unwrap_or(0), minutes.parse().unwrap_or(0))
+        }
+        _ => (0, 0),
+    };
+    Duration::minutes(sign * (hours * 60 + minutes))
+}
+
+/// 01:00 on the last sunday of a month with 31 days.
+fn last_sunday(year: i32, month: u32) -> NaiveDateTime {
+    let last = NaiveDate::from_ymd(year, month, 31);
+    (last - Duration::days(last.weekday().num_days_from_sunday().into())).and_hms(1, 0, 0)
+}
+
+/// A point in time to filter from, either a date (`2024-01-01`, `2024-01-01T12:00:00+02:00`)
+/// or a time ago (`12h`, `7d`, `2w`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Since {
+    Date(DateTime<Utc>),
+    Ago(Duration),
+}
+
+impl Since {
+    /// The moment it stands for, with times ago counted back from `now`.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Since::Date(date) => *date,
+            // a time ago from before the earliest date chrono knows is from always
+            Since::Ago(ago) => now.checked_sub_signed(*ago).unwrap_or(MIN_DATETIME),
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Since::Date(date.with_timezone(&Utc)));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Since::Date(Utc.from_utc_datetime(&date.and_hms(0, 0, 0))));
+        }
+
+        let invalid = || {
+            format!(
+                "invalid date '{}', expected something like 2024-01-01 or 7d",
+                s
+            )
+        };
+        let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let count: i64 = s[..split].parse().map_err(|_| invalid())?;
+        let unit = match &s[split..] {
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        // Duration::seconds panics past i64::MAX milliseconds
+        let seconds = count
+            .checked_mul(unit)
+            .filter(|seconds| *seconds <= Duration::max_value().num_seconds())
+            .ok_or_else(|| format!("'{}' is too long ago", s))?;
+        let ago = Duration::seconds(seconds);
+        if Utc::now().checked_sub_signed(ago).is_none() {
+            return Err(format!("'{}' is too long ago", s));
+        }
+        Ok(Since::Ago(ago))
+    }
+}
+
+impl TryFrom<String> for Since {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::date::{parse_uploaded, Since};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn parses_uploaded_dates() {
+        // the fixture's first movie, with its date_uploaded_unix
+        let summer = Utc.timestamp(1622036366, 0);
+        assert_eq!(
+            parse_uploaded("2021-05-26 15:39:26", Some("CET")),
+            Some(summer)
+        );
+        assert_eq!(
+            parse_uploaded("2021-01-26 15:39:26", Some("cet")),
+            Some(Utc.ymd(2021, 1, 26).and_hms(14, 39, 26))
+        );
+        assert_eq!(parse_uploaded("2021-05-26 13:39:26", None), Some(summer));
+        assert_eq!(
+            parse_uploaded("2021-05-26 16:09:26", Some("+02:30")),
+            Some(summer)
+        );
+        assert_eq!(
+            parse_uploaded("2021-05-26 08:39:26", Some("UTC-5")),
+            Some(summer)
+        );
+        assert_eq!(parse_uploaded("yesterday", Some("CET")), None);
+    }
+
+    #[test]
+    fn parses_since() {
+        let now = Utc.ymd(2024, 3, 10).and_hms(12, 0, 0);
+        let since = |s: &str| s.parse::<Since>().map(|since| since.cutoff(now));
+        assert_eq!(
+            since("2024-01-01"),
+            Ok(Utc.ymd(2024, 1, 1).and_hms(0, 0, 0))
+        );
+        assert_eq!(
+            since("2024-01-01T12:00:00+02:00"),
+            Ok(Utc.ymd(2024, 1, 1).and_hms(10, 0, 0))
+        );
+        assert_eq!(since("7d"), Ok(now - Duration::days(7)));
+        assert_eq!(since("12h"), Ok(now - Duration::hours(12)));
+        assert_eq!(since("2w"), Ok(now - Duration::days(14)));
+        assert!(since("7y").is_err());
+        assert!(since("d").is_err());
+        assert!(since("last week").is_err());
+        assert_eq!(
+            since("99999999999999999d"),
+            Err("'99999999999999999d' is too long ago".to_string())
+        );
+        assert!(since("9999999999w").is_err());
+        assert!(
+            since("5000000w").is_ok(),
+            "some 96000 years back is still a date"
+        );
+        assert_eq!(
+            Since::Ago(Duration::max_value()).cutoff(now),
+            chrono::MIN_DATETIME
+        );
+    }
+}
@@ -0,0 +1,648 @@
+use crate::parse::api::{Data, ListResponse, Movie};
+use crate::request::{Genre, ListRequest, MovieRef, Order, Sort};
+use chrono::{DateTime, TimeZone, Utc};
+use log::info;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::error::Error;
+use std::path::Path;
+
+/// Page size used while walking `list_movies` during a sync (the api maximum).
+const SYNC_PAGE_SIZE: u8 = 50;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS movies (
+        id INTEGER PRIMARY KEY,
+        imdb_code TEXT,
+        title TEXT,
+        year INTEGER,
+        rating REAL,
+        date_uploaded_unix INTEGER,
+        json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS movies_date_uploaded ON movies (date_uploaded_unix);
+    CREATE INDEX IF NOT EXISTS movies_imdb_code ON movies (imdb_code);
+    CREATE TABLE IF NOT EXISTS torrents (
+        movie_id INTEGER NOT NULL REFERENCES movies (id) ON DELETE CASCADE,
+        hash TEXT NOT NULL,
+        quality TEXT,
+        type TEXT,
+        seeders INTEGER,
+        peers INTEGER,
+        size_bytes INTEGER,
+        date_uploaded_unix INTEGER,
+        PRIMARY KEY (movie_id, hash)
+    );
+    CREATE TABLE IF NOT EXISTS genres (
+        movie_id INTEGER NOT NULL REFERENCES movies (id) ON DELETE CASCADE,
+        genre TEXT NOT NULL COLLATE NOCASE,
+        PRIMARY KEY (movie_id, genre)
+    );
+    CREATE TABLE IF NOT EXISTS sync_state (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        last_page INTEGER NOT NULL,
+        complete INTEGER NOT NULL
+    );
+";
+
+/// A local sqlite mirror of the yts catalogue.
+///
+/// Movies are kept whole as json next to the columns needed for filtering and sorting,
+/// so whatever comes out of the catalogue is the same `Movie` the api returned.
+pub struct Catalogue {
+    conn: Connection,
+}
+
+/// What a [`Catalogue::sync`] run did.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// The page an unfinished walk of the whole catalogue was picked up at.
+    pub resumed: Option<u32>,
+    pub pages: u32,
+    pub movies: usize,
+    pub torrents: usize,
+}
+
+impl Catalogue {
+    /// Opens (and creates if needed) the catalogue stored at `path`.
+    pub fn open(path: &Path) -> Result<Catalogue, Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Catalogue::init(Connection::open(path)?)
+    }
+
+    /// An empty catalogue that only lives as long as the returned value.
+    pub fn open_in_memory() -> Result<Catalogue, Box<dyn Error>> {
+        Catalogue::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Catalogue, Box<dyn Error>> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Catalogue { conn })
+    }
+
+    /// Inserts or replaces the given movies together with their torrents and genres.
+    /// Movies without an id are skipped. Returns the number of stored movies and torrents.
+    pub fn upsert(&mut self, movies: &[Movie]) -> Result<(usize, usize), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        let (mut movie_count, mut torrent_count) = (0, 0);
+
+        for movie in movies {
+            let id = match movie.id {
+                Some(id) => id,
+                None => continue,
+            };
+            tx.execute("DELETE FROM movies WHERE id = ?1", params![id])?;
+            tx.execute(
+                "INSERT INTO movies (id, imdb_code, title, year, rating, date_uploaded_unix, json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    movie.imdb_code,
+                    movie.title,
+                    movie.year,
+                    movie.rating,
+                    movie.date_uploaded_unix.as_ref().map(unix),
+                    serde_json::to_string(movie)?,
+                ],
+            )?;
+            movie_count += 1;
+
+            for genre in movie.genres.iter().flatten() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO genres (movie_id, genre) VALUES (?1, ?2)",
+                    params![id, genre],
+                )?;
+            }
+
+            for torrent in movie.torrents.iter().flatten() {
+                let hash = match &torrent.hash {
+                    Some(hash) => hash,
+                    None => continue,
+                };
+                tx.execute(
+                    "INSERT OR REPLACE INTO torrents
+                     (movie_id, hash, quality, type, seeders, peers, size_bytes, date_uploaded_unix)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        id,
+                        hash,
+                        torrent.quality,
+                        torrent.ty_pe,
+                        torrent.seeders,
+                        torrent.peers,
+                        torrent.size_bytes.map(|size| size as i64),
+                        torrent.date_uploaded_unix.as_ref().map(unix),
+                    ],
+                )?;
+                torrent_count += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok((movie_count, torrent_count))
+    }
+
+    /// The upload date of the most recently added movie, if there is any.
+    pub fn newest_upload(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error>> {
+        let newest: Option<i64> = self
+            .conn
+            .query_row("SELECT MAX(date_uploaded_unix) FROM movies", [], |row| {
+                row.get(0)
+            })
+            .optional()?
+            .flatten();
+
+        Ok(newest.map(|value| Utc.timestamp(value, 0)))
+    }
+
+    /// Number of movies in the catalogue.
+    pub fn movie_count(&self) -> Result<u64, Box<dyn Error>> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM movies", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    /// Every movie in the catalogue, newest first.
+    pub fn movies(&self) -> Result<Vec<Movie>, Box<dyn Error>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT json FROM movies ORDER BY date_uploaded_unix DESC, id DESC")?;
+        let movies = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|json| Ok(serde_json::from_str(&json?)?))
+            .collect::<Result<Vec<Movie>, Box<dyn Error>>>()?;
+        Ok(movies)
+    }
+
+    /// The movie with the given yts id or imdb code.
+    pub fn movie(&self, movie: &MovieRef) -> Result<Option<Movie>, Box<dyn Error>> {
+        let (condition, value) = match movie {
+            MovieRef::Id(id) => ("id = ?1", Value::Integer((*id).into())),
+            MovieRef::Imdb(code) => ("imdb_code = ?1", Value::Text(code.clone())),
+        };
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                &format!("SELECT json FROM movies WHERE {} LIMIT 1", condition),
+                params![value],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(match json {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        })
+    }
+
+    /// How many movies there are in each genre, by genre name.
+    pub fn genre_counts(&self) -> Result<Vec<(String, u64)>, Box<dyn Error>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT genre, COUNT(*) FROM genres GROUP BY genre ORDER BY genre")?;
+        let counts = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, u64)>, _>>()?;
+        Ok(counts)
+    }
+
+    /// The movie one of whose torrents has the given info-hash.
+    pub fn movie_by_hash(&self, hash: &str) -> Result<Option<Movie>, Box<dyn Error>> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT m.json FROM movies m JOIN torrents t ON t.movie_id = m.id
+                 WHERE t.hash = ?1 COLLATE NOCASE LIMIT 1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(match json {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        })
+    }
+
+    /// Walks `list_movies` from the newest additions backwards and stores everything it sees.
+    ///
+    /// Until a walk of the whole catalogue has finished, every sync picks it up after the last
+    /// page it stored, so a first sync that fails halfway doesn't leave the older movies out for
+    /// good. Afterwards it stops after the first page that reaches into movies that are already
+    /// known, unless `full` is set, in which case the whole catalogue is walked again.
+    pub fn sync(&mut self, mirror: Option<&str>, full: bool) -> Result<SyncReport, Box<dyn Error>> {
+        self.sync_with(full, |page| {
+            let mut request = ListRequest::new();
+            request
+                .limit(SYNC_PAGE_SIZE)
+                .page(page)
+                .sort_by(Sort::DateAdded)
+                .order_by(Order::Desc);
+            if let Some(mirror) = mirror {
+                request.mirror(mirror)?;
+            }
+            request.execute()
+        })
+    }
+
+    /// Does the work for [`Catalogue::sync`] using `fetch` to retrieve each page.
+    pub fn sync_with<F>(&mut self, full: bool, mut fetch: F) -> Result<SyncReport, Box<dyn Error>>
+    where
+        F: FnMut(u32) -> Result<ListResponse, Box<dyn Error>>,
+    {
+        let mut report = SyncReport::default();
+        // where the walk of the whole catalogue starts, if there is one to do
+        let walk_from = match self.sync_state()? {
+            Some((last_page, false)) => {
+                report.resumed = Some(last_page + 1);
+                Some(last_page + 1)
+            }
+            // catalogues from before the state was kept get walked once more
+            None => Some(1),
+            Some(_) if full => Some(1),
+            Some(_) => None,
+        };
+        let known = match walk_from {
+            Some(_) => None,
+            None => self.newest_upload()?,
+        };
+
+        for page in walk_from.unwrap_or(1).. {
+            let movies = match fetch(page)?.data.and_then(|data| data.movies) {
+                Some(movies) if !movies.is_empty() => movies,
+                _ => {
+                    if walk_from.is_some() {
+                        self.set_sync_state(page - 1, true)?;
+                    }
+                    break;
+                }
+            };
+
+            let (movie_count, torrent_count) = self.upsert(&movies)?;
+            report.pages += 1;
+            report.movies += movie_count;
+            report.torrents += torrent_count;
+            info!("synced page {} ({} movies)", page, movie_count);
+
+            let reached_known = match known {
+                Some(known) => movies
+                    .iter()
+                    .any(|m| matches!(m.date_uploaded_unix, Some(date) if date <= known)),
+                None => false,
+            };
+            let last = reached_known || movies.len() < SYNC_PAGE_SIZE as usize;
+            if walk_from.is_some() {
+                self.set_sync_state(page, last)?;
+            }
+            if last {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The last page stored by the walk of the whole catalogue, and whether the walk finished.
+    fn sync_state(&self) -> Result<Option<(u32, bool)>, Box<dyn Error>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT last_page, complete FROM sync_state WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    fn set_sync_state(&self, last_page: u32, complete: bool) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_state (id, last_page, complete) VALUES (1, ?1, ?2)",
+            params![last_page, complete],
+        )?;
+        Ok(())
+    }
+
+    /// Runs the filters of a list request against the catalogue instead of the api.
+    /// The rotten tomatoes flag and the mirror don't mean anything offline and are ignored,
+    /// sorting by download or like count is an error.
+    pub fn list(&self, request: &ListRequest) -> Result<ListResponse, Box<dyn Error>> {
+        let (filter, values) = conditions(request);
+        let movie_count: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM movies m {}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let limit = request.limit.unwrap_or(20);
+        let page = request.page.unwrap_or(1).max(1);
+        let movies = self.select(
+            request,
+            &format!(
+                "LIMIT {limit} OFFSET {offset}",
+                limit = limit,
+                offset = (page as u64 - 1) * limit as u64,
+            ),
+        )?;
+
+        Ok(ListResponse {
+            status: Some("ok".to_string()),
+            status_message: Some("Query was successful".to_string()),
+            data: Some(Data {
+                movie_count: Some(movie_count as u64),
+                limit: Some(limit.into()),
+                page_number: Some(page),
+                movies: Some(movies),
+            }),
+            meta: None,
+        })
+    }
+
+    /// Every movie matching the filters of the list request, in its order, like [`Catalogue::list`]
+    /// without the paging.
+    pub fn matching(&self, request: &ListRequest) -> Result<Vec<Movie>, Box<dyn Error>> {
+        self.select(request, "")
+    }
+
+    /// The movies matching the request, sorted as it asks and then cut by `limit`.
+    fn select(&self, request: &ListRequest, limit: &str) -> Result<Vec<Movie>, Box<dyn Error>> {
+        let (filter, values) = conditions(request);
+        let sort_column = match request.sort_by.unwrap_or(Sort::DateAdded) {
+            // download and like counts aren't part of the list payload, so there's nothing to sort
+            sort @ (Sort::DownloadCount | Sort::LikeCount) => {
+                return Err(
+                    format!("the catalogue can't sort by {}, yts doesn't list it", sort).into(),
+                )
+            }
+            Sort::Title => "m.title COLLATE NOCASE",
+            Sort::Year => "m.year",
+            Sort::Rating => "m.rating",
+            Sort::Peers => "(SELECT MAX(t.peers) FROM torrents t WHERE t.movie_id = m.id)",
+            Sort::Seeds => "(SELECT MAX(t.seeders) FROM torrents t WHERE t.movie_id = m.id)",
+            Sort::DateAdded => "m.date_uploaded_unix",
+        };
+        let order = match request.order_by.unwrap_or(Order::Desc) {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        };
+
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT m.json FROM movies m {filter} ORDER BY {sort} {order}, m.id {order} {limit}",
+            filter = filter,
+            sort = sort_column,
+            order = order,
+            limit = limit,
+        ))?;
+        let movies = statement
+            .query_map(params_from_iter(values.iter()), |row| {
+                row.get::<_, String>(0)
+            })?
+            .map(|json| Ok(serde_json::from_str(&json?)?))
+            .collect::<Result<Vec<Movie>, Box<dyn Error>>>()?;
+        Ok(movies)
+    }
+}
+
+/// The WHERE clause for the filters of a list request, and the values of its parameters.
+fn conditions(request: &ListRequest) -> (String, Vec<Value>) {
+    let mut conditions: Vec<&str> = vec![];
+    let mut values: Vec<Value> = vec![];
+
+    if let Some(quality) = &request.quality {
+        conditions
+            .push("EXISTS (SELECT 1 FROM torrents t WHERE t.movie_id = m.id AND t.quality = ?)");
+        values.push(Value::Text(quality.to_string()));
+    }
+    if let Some(rating) = request.minimum_rating {
+        conditions.push("m.rating >= ?");
+        values.push(Value::Real(rating.into()));
+    }
+    if let Some(term) = &request.query_term {
+        conditions.push("(m.title LIKE ? OR m.imdb_code = ?)");
+        values.push(Value::Text(format!("%{}%", term)));
+        values.push(Value::Text(term.to_string()));
+    }
+    match request.genre {
+        Some(Genre::All) | None => {}
+        Some(genre) => {
+            conditions
+                .push("EXISTS (SELECT 1 FROM genres g WHERE g.movie_id = m.id AND g.genre = ?)");
+            values.push(Value::Text(genre.to_string()));
+        }
+    }
+
+    let filter = if conditions.is_empty() {
+        "".to_string()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    (filter, values)
+}
+
+/// The stored value for upload dates: unix seconds.
+/// Databases from before the api models read `date_uploaded_unix` as seconds stay valid: back
+/// then the raw value ended up in the nanosecond part of the date and got stored as such, so
+/// the old nanosecond value is the same number as the seconds stored now.
+fn unix(date: &DateTime<Utc>) -> i64 {
+    date.timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Catalogue;
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::request::{Genre, ListRequest, MovieRef, Order, Quality, Sort};
+    use chrono::{TimeZone, Utc};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn catalogue() -> Catalogue {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let mut catalogue = Catalogue::open_in_memory().unwrap();
+        catalogue
+            .upsert(&response.data.unwrap().movies.unwrap())
+            .unwrap();
+        catalogue
+    }
+
+    fn titles(response: ListResponse) -> Vec<String> {
+        response
+            .data
+            .unwrap()
+            .movies
+            .unwrap()
+            .iter()
+            .map(|m| m.title())
+            .collect()
+    }
+
+    #[test]
+    fn sync_stops_at_known_movies() {
+        let mut catalogue = Catalogue::open_in_memory().unwrap();
+        let mut fetched = vec![];
+        let report = catalogue
+            .sync_with(false, |page| {
+                fetched.push(page);
+                Ok(serde_json::from_str(JSON)?)
+            })
+            .unwrap();
+        // the fixture page is short, so the first sync ends after it
+        assert_eq!(fetched, vec![1]);
+        assert_eq!(report.movies, 2);
+        assert_eq!(catalogue.movie_count().unwrap(), 2);
+
+        let report = catalogue
+            .sync_with(false, |_| Ok(serde_json::from_str(JSON)?))
+            .unwrap();
+        assert_eq!(report.pages, 1);
+        assert_eq!(catalogue.movie_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn sync_resumes_unfinished_walks() {
+        let movie = catalogue().movies().unwrap().remove(0);
+        // pages of 50 movies, newest first, with a short fourth page
+        let page = |page: u32| -> ListResponse {
+            let count = if page < 4 { 50 } else { 10 };
+            let movies = (0..count)
+                .map(|i| {
+                    let id = 1000 - (page - 1) * 50 - i;
+                    let mut movie = movie.clone();
+                    movie.id = Some(id);
+                    movie.torrents = None;
+                    movie.date_uploaded_unix = Some(Utc.timestamp(id.into(), 0));
+                    movie
+                })
+                .collect::<Vec<_>>();
+            movies.into()
+        };
+
+        let mut catalogue = Catalogue::open_in_memory().unwrap();
+        let failed = catalogue.sync_with(false, |n| match n {
+            3 => Err("connection reset".into()),
+            n => Ok(page(n)),
+        });
+        assert!(failed.is_err());
+        assert_eq!(catalogue.movie_count().unwrap(), 100);
+
+        let mut fetched = vec![];
+        let report = catalogue
+            .sync_with(false, |n| {
+                fetched.push(n);
+                Ok(page(n))
+            })
+            .unwrap();
+        assert_eq!(fetched, vec![3, 4], "picks up where it stopped");
+        assert_eq!(report.resumed, Some(3));
+        assert_eq!(catalogue.movie_count().unwrap(), 160);
+
+        let mut fetched = vec![];
+        catalogue
+            .sync_with(false, |n| {
+                fetched.push(n);
+                Ok(page(n))
+            })
+            .unwrap();
+        assert_eq!(
+            fetched,
+            vec![1],
+            "finished walks make for incremental syncs"
+        );
+    }
+
+    #[test]
+    fn newest_upload() {
+        let newest = catalogue().newest_upload().unwrap().unwrap();
+        assert_eq!(newest.timestamp(), 1622036366);
+    }
+
+    #[test]
+    fn movie_by_hash() {
+        let catalogue = catalogue();
+        let movie = catalogue
+            .movie_by_hash("8247d498ab7f38537e99ebf8b1e87943797e49da")
+            .unwrap();
+        assert_eq!(
+            movie.map(|m| m.title()),
+            Some("The Dating List".to_string())
+        );
+        assert!(catalogue.movie_by_hash("nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn movie() {
+        let catalogue = catalogue();
+        let title = |movie: MovieRef| catalogue.movie(&movie).unwrap().map(|m| m.title());
+        assert_eq!(
+            title(MovieRef::Id(32080)).as_deref(),
+            Some("La via dei babbuini")
+        );
+        assert_eq!(
+            title(MovieRef::Imdb("tt0144665".into())).as_deref(),
+            Some("La via dei babbuini")
+        );
+        assert_eq!(title(MovieRef::Id(1)), None);
+    }
+
+    #[test]
+    fn list_filters() {
+        let catalogue = catalogue();
+
+        let all = catalogue.list(&ListRequest::new()).unwrap();
+        assert_eq!(all.data.as_ref().unwrap().movie_count, Some(2));
+
+        let mut request = ListRequest::new();
+        request.sort_by(Sort::Title);
+        assert_eq!(titles(catalogue.list(&request).unwrap()).len(), 2);
+
+        let mut request = ListRequest::new();
+        request.sort_by(Sort::LikeCount);
+        assert!(catalogue.list(&request).is_err());
+
+        let mut request = ListRequest::new();
+        request.query_term("babbuini".to_string());
+        assert_eq!(
+            titles(catalogue.list(&request).unwrap()),
+            vec!["La via dei babbuini"]
+        );
+
+        let mut request = ListRequest::new();
+        request
+            .genre(Genre::Comedy)
+            .quality(Quality::Quality720p)
+            .rating(6);
+        assert_eq!(
+            titles(catalogue.list(&request).unwrap()),
+            vec!["La via dei babbuini"]
+        );
+
+        let mut request = ListRequest::new();
+        request.quality(Quality::Quality3D);
+        assert!(titles(catalogue.list(&request).unwrap()).is_empty());
+
+        let mut request = ListRequest::new();
+        request
+            .sort_by(Sort::Year)
+            .order_by(Order::Asc)
+            .limit(1)
+            .page(2);
+        assert_eq!(
+            titles(catalogue.list(&request).unwrap()),
+            vec!["The Dating List"]
+        );
+        let matching = catalogue.matching(&request).unwrap();
+        assert_eq!(
+            matching.iter().map(Movie::title).collect::<Vec<_>>(),
+            vec!["La via dei babbuini", "The Dating List"],
+            "all of them, without the paging"
+        );
+    }
+
+    #[test]
+    fn genre_counts() {
+        assert_eq!(
+            catalogue().genre_counts().unwrap(),
+            vec![("Action".to_string(), 1), ("Comedy".to_string(), 1)]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::client;
+use crate::parse::api::{Movie, Torrent};
+use crate::request::Quality;
+use crate::store::temporary_path;
+use reqwest::header::HeaderMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// The default name for downloaded .torrent files.
+pub const DEFAULT_NAME_TEMPLATE: &str = "{title} ({year}) [{quality}].torrent";
+
+/// The torrent that fits the wanted quality and type best. When one of them isn't given,
+/// higher qualities and bluray rips are preferred. Ties go to the torrent with the most seeders.
+pub fn best_torrent<'a>(
+    movie: &'a Movie,
+    quality: Option<Quality>,
+    ty_pe: Option<&str>,
+) -> Option<&'a Torrent> {
+    let quality = quality.map(|q| q.to_string());
+
+    movie
+        .torrents
+        .iter()
+        .flatten()
+        .filter(|t| match &quality {
+            Some(quality) => t.quality.as_deref() == Some(quality.as_str()),
+            None => true,
+        })
+        .filter(|t| match ty_pe {
+            Some(ty_pe) => t
+                .ty_pe
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(ty_pe)),
+            None => true,
+        })
+        .max_by_key(|t| {
+            (
+                quality_rank(t.quality.as_deref()),
+                type_rank(t.ty_pe.as_deref()),
+                t.seeders.unwrap_or(0),
+            )
+        })
+}
+
+/// Higher for better qualities, 3D and unknown ones being the lowest.
+pub(crate) fn quality_rank(quality: Option<&str>) -> u8 {
+    match quality {
+        Some("2160p") => 3,
+        Some("1080p") => 2,
+        Some("720p") => 1,
+        _ => 0,
+    }
+}
+
+fn type_rank(ty_pe: Option<&str>) -> u8 {
+    match ty_pe {
+        Some(t) if t.eq_ignore_ascii_case("bluray") => 1,
+        _ => 0,
+    }
+}
+
+/// Fills in a file name template. Known placeholders are
+/// `{title}`, `{year}`, `{quality}`, `{type}`, `{id}`, `{imdb}` and `{hash}`.
+/// Whatever can't be part of a file name gets replaced.
+pub fn file_name(template: &str, movie: &Movie, torrent: &Torrent) -> String {
+    let name = template
+        .replace("{title}", &movie.title())
+        .replace("{year}", movie.year().trim())
+        .replace("{quality}", torrent.quality.as_deref().unwrap_or_default())
+        .replace("{type}", torrent.ty_pe.as_deref().unwrap_or_default())
+        .replace("{id}", &movie.id())
+        .replace("{imdb}", movie.imdb_code.as_deref().unwrap_or_default())
+        .replace("{hash}", torrent.hash.as_deref().unwrap_or_default());
+
+    sanitize(&name)
+}
+
+/// Makes a string safe to use as a file name on every common platform.
+pub fn sanitize(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let name = name.trim().trim_start_matches('.').trim_end_matches('.');
+
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Where the torrent file can be downloaded from, on the given mirror.
+pub fn torrent_url(torrent: &Torrent, mirror: Option<&str>) -> Result<Url, Box<dyn Error>> {
+    let mut url = torrent
+        .url
+        .clone()
+        .ok_or("the torrent has no download url")?;
+    if let Some(mirror) = mirror {
+        url.set_host(Some(mirror))?;
+    }
+    Ok(url)
+}
+
+/// Downloads a torrent file.
+pub fn fetch(url: &Url) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = client::shared()?
+        .get(url, HeaderMap::new())?
+        .error_for_status()?
+        .bytes()?;
+    // torrent files are bencoded dictionaries, anything else is likely an error page
+    if !bytes.starts_with(b"d") {
+        return Err(format!("{} didn't return a torrent file", url).into());
+    }
+    Ok(bytes.to_vec())
+}
+
+/// Downloads the torrent file into `dir` and returns the path it was saved to.
+///
+/// The file is written under a temporary name and only renamed into place once `check` accepts
+/// it, so a download that fails the check leaves nothing behind. A file that's already there is
+/// only replaced with `overwrite`.
+pub fn download<F>(
+    url: &Url,
+    dir: &Path,
+    file_name: &str,
+    overwrite: bool,
+    check: F,
+) -> Result<PathBuf, Box<dyn Error>>
+where
+    F: FnOnce(&Path) -> Result<(), Box<dyn Error>>,
+{
+    let path = dir.join(file_name);
+    let exists = || -> Result<(), Box<dyn Error>> {
+        if !overwrite && path.exists() {
+            return Err(format!("{} already exists", path.display()).into());
+        }
+        Ok(())
+    };
+    exists()?;
+
+    let bytes = fetch(url)?;
+    std::fs::create_dir_all(dir)?;
+    let temporary = temporary_path(&path);
+    std::fs::write(&temporary, &bytes)?;
+    let saved = check(&temporary)
+        .and_then(|_| exists())
+        .and_then(|_| Ok(std::fs::rename(&temporary, &path)?));
+    if saved.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+    saved.map(|_| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::download::{
+        best_torrent, download, file_name, sanitize, torrent_url, DEFAULT_NAME_TEMPLATE,
+    };
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::request::Quality;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movie() -> Movie {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let mut movie = response.data.unwrap().movies.unwrap().remove(1);
+        let torrents = movie.torrents.as_mut().unwrap();
+        torrents[0].seeders = Some(3);
+        let mut bluray = torrents[0].clone();
+        bluray.ty_pe = Some("bluray".to_string());
+        bluray.hash = Some("BLURAY".to_string());
+        bluray.seeders = Some(1);
+        torrents.push(bluray);
+        movie
+    }
+
+    #[test]
+    fn picks_the_best_torrent() {
+        let movie = movie();
+        let quality = |t: Option<&crate::parse::api::Torrent>| t.and_then(|t| t.quality.clone());
+
+        assert_eq!(
+            quality(best_torrent(&movie, None, None)).as_deref(),
+            Some("1080p")
+        );
+        assert_eq!(
+            best_torrent(&movie, Some(Quality::Quality720p), None)
+                .unwrap()
+                .hash
+                .as_deref(),
+            Some("BLURAY")
+        );
+        assert_eq!(
+            best_torrent(&movie, Some(Quality::Quality720p), Some("WEB"))
+                .unwrap()
+                .ty_pe
+                .as_deref(),
+            Some("web")
+        );
+        assert!(best_torrent(&movie, Some(Quality::Quality2160p), None).is_none());
+    }
+
+    #[test]
+    fn ties_go_to_seeders() {
+        let mut movie = movie();
+        let torrents = movie.torrents.as_mut().unwrap();
+        let mut other = torrents[0].clone();
+        other.hash = Some("OTHER".to_string());
+        other.seeders = Some(30);
+        torrents.push(other);
+
+        let torrent = best_torrent(&movie, Some(Quality::Quality720p), Some("web")).unwrap();
+        assert_eq!(torrent.hash.as_deref(), Some("OTHER"));
+    }
+
+    #[test]
+    fn file_names() {
+        let mut movie = movie();
+        let torrent = best_torrent(&movie, None, None).unwrap().clone();
+        assert_eq!(
+            file_name(DEFAULT_NAME_TEMPLATE, &movie, &torrent),
+            "The Dating List (2019) [1080p].torrent"
+        );
+
+        movie.title = Some("Face/Off: \"Director's\" Cut?".to_string());
+        assert_eq!(
+            file_name("{title} {type}.torrent", &movie, &torrent),
+            "Face_Off_ _Director's_ Cut_ web.torrent"
+        );
+        assert_eq!(sanitize("../.."), "_");
+        assert_eq!(sanitize(" ..hidden"), "hidden");
+    }
+
+    #[test]
+    fn mirrored_url() {
+        let movie = movie();
+        let torrent = &movie.torrents.as_ref().unwrap()[0];
+        let url = torrent_url(torrent, Some("yts.lt")).unwrap();
+        assert_eq!(url.host_str(), Some("yts.lt"));
+        assert!(url.path().starts_with("/torrent/download/"));
+    }
+
+    #[test]
+    fn keeps_existing_files() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/torrent")
+            .with_body("d4:infoe")
+            .create();
+        let url = url::Url::parse(&format!("{}/torrent", server.url())).unwrap();
+        let dir = std::env::temp_dir().join(format!("yts-download-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("movie.torrent");
+        std::fs::write(&path, "mine").unwrap();
+        let contents = || std::fs::read_to_string(&path).unwrap();
+
+        assert!(download(&url, &dir, "movie.torrent", false, |_| Ok(())).is_err());
+        assert_eq!(contents(), "mine");
+        let rejected = download(&url, &dir, "movie.torrent", true, |_| {
+            Err("wrong info-hash".into())
+        });
+        assert!(rejected.is_err());
+        assert_eq!(contents(), "mine", "failed checks leave the old file alone");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        download(&url, &dir, "movie.torrent", true, |_| Ok(())).unwrap();
+        assert_eq!(contents(), "d4:infoe");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
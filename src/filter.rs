@@ -0,0 +1,344 @@
+use crate::date::Since;
+use crate::imdb::{ImdbData, ImdbInfo};
+use crate::parse::api::{ListResponse, Movie};
+use crate::size::{ByteSize, SizeRange};
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Filters applied to movies after they have been retrieved,
+/// for everything the api itself can't filter on.
+#[derive(Debug, Default, Clone)]
+pub struct PostFilter {
+    min_rating: Option<f32>,
+    min_year: Option<u16>,
+    max_year: Option<u16>,
+    language: Option<String>,
+    sizes: SizeRange,
+    since: Option<Since>,
+    excluded: HashSet<u32>,
+    imdb: Option<Arc<ImdbData>>,
+    min_votes: Option<u32>,
+    director: Option<String>,
+    original_title: Option<String>,
+}
+
+impl PostFilter {
+    pub fn new() -> PostFilter {
+        PostFilter::default()
+    }
+
+    /// Fractional minimum rating, as opposed to the api's whole numbers.
+    pub fn min_rating(&mut self, min_rating: f32) -> &mut Self {
+        self.min_rating = Some(min_rating);
+        self
+    }
+    pub fn min_year(&mut self, min_year: u16) -> &mut Self {
+        self.min_year = Some(min_year);
+        self
+    }
+    pub fn max_year(&mut self, max_year: u16) -> &mut Self {
+        self.max_year = Some(max_year);
+        self
+    }
+    /// Two letter language code, e.g. "en".
+    pub fn language(&mut self, language: String) -> &mut Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Only keeps the torrents at least this big, and the movies that have any left.
+    pub fn min_size(&mut self, min_size: ByteSize) -> &mut Self {
+        self.sizes.min = Some(min_size);
+        self
+    }
+    /// Only keeps the torrents at most this big, and the movies that have any left.
+    pub fn max_size(&mut self, max_size: ByteSize) -> &mut Self {
+        self.sizes.max = Some(max_size);
+        self
+    }
+
+    /// Only keeps the movies uploaded since then.
+    pub fn since(&mut self, since: Since) -> &mut Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Leaves out the movies with these ids, e.g. the ones already seen.
+    pub fn exclude(&mut self, ids: impl IntoIterator<Item = u32>) -> &mut Self {
+        self.excluded.extend(ids);
+        self
+    }
+
+    /// The IMDb datasets to enrich the movies with and to filter by votes, directors and
+    /// original titles with.
+    pub fn imdb(&mut self, imdb: Arc<ImdbData>) -> &mut Self {
+        self.imdb = Some(imdb);
+        self
+    }
+    pub fn min_votes(&mut self, min_votes: u32) -> &mut Self {
+        self.min_votes = Some(min_votes);
+        self
+    }
+    /// Part of a director's name, as the datasets have it (`nm` ids without name.basics).
+    pub fn director(&mut self, director: String) -> &mut Self {
+        self.director = Some(director.to_lowercase());
+        self
+    }
+    /// Part of the original title, e.g. of a movie known by its english title.
+    pub fn original_title(&mut self, original_title: String) -> &mut Self {
+        self.original_title = Some(original_title.to_lowercase());
+        self
+    }
+
+    /// Whether it filters on something only the IMDb datasets know.
+    /// The IMDb data the filter was given, if any.
+    pub fn imdb_data(&self) -> Option<&Arc<ImdbData>> {
+        self.imdb.as_ref()
+    }
+
+    /// How many of the movies the IMDb filters had to let through for lack of data.
+    pub fn unknown_to_imdb(&self, movies: &[Movie]) -> usize {
+        if !self.needs_imdb() {
+            return 0;
+        }
+        movies
+            .iter()
+            .filter(|movie| {
+                self.imdb
+                    .as_ref()
+                    .and_then(|imdb| imdb.get(movie))
+                    .is_none()
+            })
+            .count()
+    }
+
+    pub fn needs_imdb(&self) -> bool {
+        self.min_votes.is_some() || self.director.is_some() || self.original_title.is_some()
+    }
+
+    pub fn matches(&self, movie: &Movie) -> bool {
+        self.matches_in(movie, None)
+    }
+
+    /// Like [`PostFilter::matches`], with upload date strings read in the given timezone.
+    fn matches_in(&self, movie: &Movie, timezone: Option<&str>) -> bool {
+        if matches!(movie.id, Some(id) if self.excluded.contains(&id)) {
+            return false;
+        }
+        if let Some(min_rating) = self.min_rating {
+            if movie.rating.unwrap_or(0.0) < min_rating {
+                return false;
+            }
+        }
+        if let Some(min_year) = self.min_year {
+            if movie.year.unwrap_or(0) < min_year {
+                return false;
+            }
+        }
+        if let Some(max_year) = self.max_year {
+            if movie.year.is_none_or(|year| year > max_year) {
+                return false;
+            }
+        }
+        if let Some(language) = &self.language {
+            match &movie.language {
+                Some(movie_language) if movie_language.eq_ignore_ascii_case(language) => {}
+                _ => return false,
+            }
+        }
+        if let Some(since) = self.since {
+            if movie
+                .uploaded(timezone)
+                .is_none_or(|uploaded| uploaded < since.cutoff(Utc::now()))
+            {
+                return false;
+            }
+        }
+        if self.needs_imdb() && !self.matches_imdb(movie) {
+            return false;
+        }
+        if !self.sizes.is_open()
+            && !movie
+                .torrents
+                .iter()
+                .flatten()
+                .any(|t| self.sizes.contains(t.bytes()))
+        {
+            return false;
+        }
+        true
+    }
+
+    /// Movies the IMDb data doesn't know, e.g. the ones added since it was built, can't be told
+    /// apart and are kept, see [`PostFilter::unknown_to_imdb`].
+    fn matches_imdb(&self, movie: &Movie) -> bool {
+        let info: &ImdbInfo = match self.imdb.as_ref().and_then(|imdb| imdb.get(movie)) {
+            Some(info) => info,
+            None => return true,
+        };
+        if let Some(min_votes) = self.min_votes {
+            if info.votes.unwrap_or(0) < min_votes {
+                return false;
+            }
+        }
+        if let Some(director) = &self.director {
+            if !info
+                .directors
+                .iter()
+                .any(|name| name.to_lowercase().contains(director))
+            {
+                return false;
+            }
+        }
+        if let Some(original_title) = &self.original_title {
+            if !info
+                .original_title
+                .as_ref()
+                .is_some_and(|title| title.to_lowercase().contains(original_title))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Drops the movies of a list response that don't match, as well as the torrents out of the
+    /// size range.
+    pub fn apply(&self, response: &mut ListResponse) {
+        let timezone = response.timezone().map(String::from);
+        if let Some(movies) = response.data.as_mut().and_then(|d| d.movies.as_mut()) {
+            movies.retain(|movie| self.matches_in(movie, timezone.as_deref()));
+            if !self.sizes.is_open() {
+                for movie in movies {
+                    movie.retain_sizes(&self.sizes);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::PostFilter;
+    use crate::imdb::{ImdbData, ImdbInfo};
+    use crate::parse::api::ListResponse;
+    use crate::size::ByteSize;
+    use chrono::Utc;
+    use std::sync::Arc;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn titles(filter: &PostFilter) -> Vec<String> {
+        let mut response: ListResponse = serde_json::from_str(JSON).unwrap();
+        filter.apply(&mut response);
+        response
+            .data
+            .unwrap()
+            .movies
+            .unwrap()
+            .iter()
+            .map(|m| m.title())
+            .collect()
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(titles(&PostFilter::new()).len(), 2);
+        assert_eq!(
+            titles(PostFilter::new().min_rating(6.5)),
+            vec!["La via dei babbuini"]
+        );
+        assert_eq!(
+            titles(PostFilter::new().min_year(2000)),
+            vec!["The Dating List"]
+        );
+        assert_eq!(
+            titles(PostFilter::new().max_year(2000).language("IT".into())),
+            vec!["La via dei babbuini"]
+        );
+        assert_eq!(
+            titles(PostFilter::new().exclude(vec![32080])),
+            vec!["The Dating List"]
+        );
+    }
+
+    #[test]
+    fn filters_upload_dates() {
+        let since = |s: &str| {
+            let mut filter = PostFilter::new();
+            filter.since(s.parse().unwrap());
+            titles(&filter)
+        };
+        // uploaded at 13:39:26 and 13:09:32 UTC
+        assert_eq!(since("2021-05-26T13:30:00Z"), vec!["La via dei babbuini"]);
+        assert_eq!(since("2021-05-26").len(), 2);
+        assert!(since("7d").is_empty());
+    }
+
+    #[test]
+    fn filters_sizes() {
+        let mut response: ListResponse = serde_json::from_str(JSON).unwrap();
+        PostFilter::new()
+            .min_size("900MiB".parse().unwrap())
+            .max_size("1.5GiB".parse().unwrap())
+            .apply(&mut response);
+        let movies = response.movies();
+        assert_eq!(movies.len(), 2);
+        assert_eq!(movies[0].torrents.as_ref().unwrap().len(), 1);
+        assert_eq!(
+            movies[1].torrents.as_ref().unwrap()[0].bytes(),
+            Some(ByteSize(1578400481))
+        );
+
+        assert_eq!(
+            titles(PostFilter::new().max_size("900MB".parse().unwrap())),
+            vec!["The Dating List"]
+        );
+    }
+
+    #[test]
+    fn filters_imdb() {
+        let info = ImdbInfo {
+            original_title: Some("La via dei babbuini".into()),
+            votes: Some(312),
+            directors: vec!["Luigi Magni".into()],
+            ..ImdbInfo::default()
+        };
+        let dating = ImdbInfo {
+            votes: Some(40),
+            ..ImdbInfo::default()
+        };
+        let imdb = Arc::new(ImdbData {
+            built: Utc::now(),
+            titles: [
+                ("tt0144665".to_string(), info),
+                ("tt10053944".to_string(), dating),
+            ]
+            .into(),
+        });
+        let filtered = |filter: &mut PostFilter| titles(filter.imdb(imdb.clone()));
+
+        assert_eq!(filtered(&mut PostFilter::new()).len(), 2);
+        assert_eq!(
+            filtered(PostFilter::new().min_votes(300)),
+            vec!["La via dei babbuini"]
+        );
+        assert!(filtered(PostFilter::new().min_votes(1000)).is_empty());
+        assert_eq!(
+            filtered(PostFilter::new().director("magni".into())),
+            vec!["La via dei babbuini"]
+        );
+        assert_eq!(
+            filtered(PostFilter::new().original_title("Babbuini".into())).len(),
+            1
+        );
+
+        let mut unknown = ImdbData::clone(&imdb);
+        unknown.titles.remove("tt10053944");
+        let mut filter = PostFilter::new();
+        filter.imdb(Arc::new(unknown)).min_votes(300);
+        assert_eq!(titles(&filter).len(), 2, "movies without data are kept");
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        assert_eq!(filter.unknown_to_imdb(response.movies()), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::parse::api::Movie;
+use crate::store::{load_json, save_json};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// What the IMDb datasets add to a movie.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ImdbInfo {
+    pub original_title: Option<String>,
+    pub votes: Option<u32>,
+    pub rating: Option<f32>,
+    /// Names, or `nm` ids when the datasets came without name.basics.
+    pub directors: Vec<String>,
+    pub writers: Vec<String>,
+}
+
+/// The IMDb dataset rows of the movies of the local catalogue, keyed by imdb code.
+///
+/// Built from the `title.basics`, `title.ratings` and `title.crew` dumps of
+/// <https://datasets.imdbws.com>, plus `name.basics` for the names of the crew if it's there.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImdbData {
+    pub built: DateTime<Utc>,
+    pub titles: BTreeMap<String, ImdbInfo>,
+}
+
+impl ImdbData {
+    /// Reads the datasets in `dir`, keeping the given imdb codes.
+    /// Each dataset is read from `<name>.tsv.gz`, or else from an already unpacked `<name>.tsv`.
+    pub fn from_datasets(dir: &Path, codes: &HashSet<String>) -> Result<ImdbData, Box<dyn Error>> {
+        let mut titles: BTreeMap<String, ImdbInfo> = BTreeMap::new();
+
+        read_tsv(dir, "title.basics", true, |row| {
+            if codes.contains(row[0]) {
+                titles.entry(row[0].to_string()).or_default().original_title =
+                    field(row, 3).map(String::from);
+            }
+        })?;
+        read_tsv(dir, "title.ratings", true, |row| {
+            if let Some(info) = titles.get_mut(row[0]) {
+                info.rating = field(row, 1).and_then(|rating| rating.parse().ok());
+                info.votes = field(row, 2).and_then(|votes| votes.parse().ok());
+            }
+        })?;
+        read_tsv(dir, "title.crew", true, |row| {
+            if let Some(info) = titles.get_mut(row[0]) {
+                let people = |column| {
+                    field(row, column)
+                        .map(|people| people.split(',').map(String::from).collect())
+                        .unwrap_or_default()
+                };
+                info.directors = people(1);
+                info.writers = people(2);
+            }
+        })?;
+
+        let people: HashSet<String> = titles
+            .values()
+            .flat_map(|info| info.directors.iter().chain(&info.writers))
+            .cloned()
+            .collect();
+        let mut names: HashMap<String, String> = HashMap::new();
+        read_tsv(dir, "name.basics", false, |row| {
+            if people.contains(row[0]) {
+                if let Some(name) = field(row, 1) {
+                    names.insert(row[0].to_string(), name.to_string());
+                }
+            }
+        })?;
+        for info in titles.values_mut() {
+            for person in info.directors.iter_mut().chain(info.writers.iter_mut()) {
+                if let Some(name) = names.get(person) {
+                    *person = name.clone();
+                }
+            }
+        }
+
+        Ok(ImdbData {
+            built: Utc::now(),
+            titles,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<ImdbData, Box<dyn Error>> {
+        load_json(path, "IMDb data")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        save_json(path, self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.titles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.titles.is_empty()
+    }
+
+    pub fn get(&self, movie: &Movie) -> Option<&ImdbInfo> {
+        self.titles.get(movie.imdb_code.as_deref()?)
+    }
+}
+
+/// `\N` is how the datasets leave a field empty.
+fn field<'a>(row: &[&'a str], column: usize) -> Option<&'a str> {
+    row.get(column).copied().filter(|value| *value != "\\N")
+}
+
+/// Calls `row` for every row after the header. Datasets that aren't there are an error only
+/// when they're required.
+fn read_tsv<F>(dir: &Path, name: &str, required: bool, mut row: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&[&str]),
+{
+    let gz = dir.join(format!("{}.tsv.gz", name));
+    let plain = dir.join(format!("{}.tsv", name));
+    let reader: Box<dyn Read> = if gz.exists() {
+        Box::new(GzDecoder::new(File::open(gz)?))
+    } else if plain.exists() {
+        Box::new(File::open(plain)?)
+    } else if required {
+        return Err(format!("{} has no {}.tsv.gz", dir.display(), name).into());
+    } else {
+        return Ok(());
+    };
+
+    for line in BufReader::new(reader).lines().skip(1) {
+        let line = line.map_err(|e| format!("reading {}: {}", name, e))?;
+        row(&line.split('\t').collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::imdb::{ImdbData, ImdbInfo};
+    use crate::parse::api::{ListResponse, Movie};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::collections::HashSet;
+    use std::io::Write;
+    use std::path::Path;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn write_gz(path: &Path, text: &str) {
+        let mut gz = GzEncoder::new(std::fs::File::create(path).unwrap(), Compression::fast());
+        gz.write_all(text.as_bytes()).unwrap();
+        gz.finish().unwrap();
+    }
+
+    #[test]
+    fn joins_datasets() {
+        let dir = std::env::temp_dir().join(format!("yts-imdb-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_gz(
+            &dir.join("title.basics.tsv.gz"),
+            "tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\truntimeMinutes\tgenres\n\
+             tt0144665\tmovie\tThe Baboons' Way\tLa via dei babbuini\t0\t1974\t\\N\t95\tComedy\n\
+             tt0068646\tmovie\tThe Godfather\tThe Godfather\t0\t1972\t\\N\t175\tCrime,Drama\n",
+        );
+        write_gz(
+            &dir.join("title.ratings.tsv.gz"),
+            "tconst\taverageRating\tnumVotes\ntt0144665\t6.8\t312\ntt0068646\t9.2\t2000000\n",
+        );
+        // unpacked, which works as well
+        std::fs::write(
+            dir.join("title.crew.tsv"),
+            "tconst\tdirectors\twriters\ntt0144665\tnm0536347\tnm0536347,nm0000001\n",
+        )
+        .unwrap();
+        write_gz(
+            &dir.join("name.basics.tsv.gz"),
+            "nconst\tprimaryName\tbirthYear\nnm0536347\tLuigi Magni\t1928\n",
+        );
+
+        let codes: HashSet<String> = ["tt0144665".to_string()].into();
+        let data = ImdbData::from_datasets(&dir, &codes).unwrap();
+        assert_eq!(data.len(), 1, "only the asked for titles are kept");
+
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let movie: &Movie = &response.movies()[0];
+        assert_eq!(
+            data.get(movie),
+            Some(&ImdbInfo {
+                original_title: Some("La via dei babbuini".into()),
+                votes: Some(312),
+                rating: Some(6.8),
+                directors: vec!["Luigi Magni".into()],
+                writers: vec!["Luigi Magni".into(), "nm0000001".into()],
+            })
+        );
+
+        std::fs::remove_file(dir.join("title.ratings.tsv.gz")).unwrap();
+        assert!(ImdbData::from_datasets(&dir, &codes).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
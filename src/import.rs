@@ -0,0 +1,315 @@
+use crate::lookup;
+use crate::parse::api::Movie;
+use crate::request::{CacheSettings, ListRequest, MovieRef};
+use crate::titles::{TitleMatch, TitleMatcher};
+use prettytable::{format, Cell, Row, Table};
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use strum::{Display, EnumString, EnumVariantNames};
+
+/// A site whose list exports can be imported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Display, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "snake_case")]
+pub enum Service {
+    /// `Date,Name,Year,Letterboxd URI`, e.g. watchlist.csv
+    Letterboxd,
+    /// `type,title,year,imdb,...` from a list or watchlist export
+    Trakt,
+    /// `Position,Const,...,Title,...,Title Type,...,Year,...`
+    Imdb,
+}
+
+impl Service {
+    /// The names the columns with the title, year, imdb code and kind of title go by.
+    fn columns(self) -> [&'static [&'static str]; 4] {
+        match self {
+            Service::Letterboxd => [&["name"], &["year"], &[], &[]],
+            Service::Trakt => [&["title"], &["year"], &["imdb", "imdb_id"], &["type"]],
+            Service::Imdb => [&["title"], &["year"], &["const"], &["title type"]],
+        }
+    }
+}
+
+/// A title of an export.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub title: String,
+    pub year: Option<u16>,
+    pub imdb_code: Option<String>,
+    /// What kind of title it is, e.g. `movie` or `tvSeries`, when the export says.
+    pub kind: Option<String>,
+}
+
+impl Entry {
+    /// Whether it's a movie rather than a show, a season or an episode.
+    pub fn is_movie(&self) -> bool {
+        let kind = self.kind.as_deref().unwrap_or_default().to_lowercase();
+        !["series", "show", "season", "episode", "person"]
+            .iter()
+            .any(|other| kind.contains(other))
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+        if let Some(year) = self.year {
+            write!(f, " ({})", year)?;
+        }
+        Ok(())
+    }
+}
+
+/// What an entry turned out to be on yts.
+#[derive(Clone, Debug)]
+pub enum Resolution {
+    Matched(Box<Movie>),
+    /// Several movies fit the entry equally well.
+    Ambiguous(Vec<Movie>),
+    Missing,
+}
+
+/// Reads the titles of a csv export, finding the columns by their headers.
+pub fn read_export<R: Read>(service: Service, export: R) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(export);
+    let headers: Vec<String> = csv
+        .headers()?
+        .iter()
+        .map(|h| h.trim_start_matches('\u{feff}').trim().to_lowercase())
+        .collect();
+    let [title, year, imdb_code, kind] = service
+        .columns()
+        .map(|names| headers.iter().position(|h| names.contains(&h.as_str())));
+    let title = title.ok_or_else(|| {
+        format!(
+            "this isn't a {} export, there's no {} column",
+            service,
+            service.columns()[0][0]
+        )
+    })?;
+
+    let mut entries = vec![];
+    for record in csv.records() {
+        let record = record?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| record.get(c))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let title = match field(Some(title)) {
+            Some(title) => title.to_string(),
+            None => continue,
+        };
+        entries.push(Entry {
+            title,
+            year: field(year).and_then(|year| year.parse().ok()),
+            imdb_code: field(imdb_code)
+                .filter(|code| code.starts_with("tt"))
+                .map(String::from),
+            kind: field(kind).map(String::from),
+        });
+    }
+    Ok(entries)
+}
+
+/// Finds the entry among the movies of the matcher, by its imdb code if it has one and otherwise
+/// by title and year, see [`TitleMatcher::find`].
+pub fn resolve_entry(entry: &Entry, matcher: &TitleMatcher) -> Resolution {
+    if let Some(movie) = entry
+        .imdb_code
+        .as_deref()
+        .and_then(|code| matcher.by_imdb(code))
+    {
+        return Resolution::Matched(Box::new(movie.clone()));
+    }
+
+    match matcher.find(&entry.title, entry.year, entry.imdb_code.as_deref()) {
+        TitleMatch::Found(movie) => Resolution::Matched(Box::new(movie.clone())),
+        TitleMatch::Ambiguous(movies) => {
+            Resolution::Ambiguous(movies.into_iter().cloned().collect())
+        }
+        TitleMatch::Missing => Resolution::Missing,
+    }
+}
+
+/// Looks the entry up through the api: by its imdb code if it has one, and otherwise (or if
+/// that finds nothing) by searching for its title.
+pub fn lookup_entry(
+    entry: &Entry,
+    cache: &CacheSettings,
+    mirror: Option<&str>,
+) -> Result<Resolution, Box<dyn Error>> {
+    if let Some(code) = &entry.imdb_code {
+        if let Some(movie) = lookup::resolve(&MovieRef::Imdb(code.clone()), cache, mirror)? {
+            return Ok(Resolution::Matched(Box::new(movie)));
+        }
+    }
+
+    let mut search = ListRequest::new();
+    search
+        .query_term(entry.title.clone())
+        .limit(20)
+        .cache(cache);
+    if let Some(mirror) = mirror {
+        search.mirror(mirror)?;
+    }
+    let found = search.execute()?;
+    Ok(resolve_entry(entry, &TitleMatcher::new(found.movies())))
+}
+
+/// The entries and what became of them as a table of result, entry and movie(s).
+/// Entries that couldn't be looked up come with the error.
+pub fn report_table(results: &[(Entry, Result<Resolution, String>)]) -> Table {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(Row::new(vec![
+        Cell::new("RESULT"),
+        Cell::new("ENTRY"),
+        Cell::new("YTS"),
+    ]));
+    for (entry, result) in results {
+        let (kind, detail) = match result {
+            Ok(Resolution::Matched(movie)) => ("matched", movie.title_long()),
+            Ok(Resolution::Ambiguous(movies)) => (
+                "ambiguous",
+                movies
+                    .iter()
+                    .map(|m| format!("{} [{}]", m.title_long(), m.id()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Ok(Resolution::Missing) => ("missing", String::new()),
+            Err(e) => ("failed", e.clone()),
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(kind),
+            Cell::new(&entry.to_string()),
+            Cell::new(&detail),
+        ]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::{read_export, resolve_entry, Entry, Resolution, Service};
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::titles::TitleMatcher;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    fn entry(title: &str, year: Option<u16>, imdb_code: Option<&str>) -> Entry {
+        Entry {
+            title: title.into(),
+            year,
+            imdb_code: imdb_code.map(String::from),
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn reads_exports() {
+        let letterboxd = "Date,Name,Year,Letterboxd URI\n\
+            2024-01-02,\"La via dei babbuini\",1974,https://boxd.it/abc\n\
+            2024-01-03,Nameless,,https://boxd.it/def\n";
+        assert_eq!(
+            read_export(Service::Letterboxd, letterboxd.as_bytes()).unwrap(),
+            vec![
+                entry("La via dei babbuini", Some(1974), None),
+                entry("Nameless", None, None)
+            ]
+        );
+
+        let trakt = "rank,listed_at,type,title,year,trakt,imdb,tmdb\n\
+            1,2024-01-02T10:00:00.000Z,movie,The Dating List,2019,1,tt10005384,2\n\
+            2,2024-01-02T10:00:00.000Z,show,Severance,2022,3,tt11280740,4\n";
+        let entries = read_export(Service::Trakt, trakt.as_bytes()).unwrap();
+        assert_eq!(entries[0].imdb_code.as_deref(), Some("tt10005384"));
+        assert!(entries[0].is_movie());
+        assert!(!entries[1].is_movie());
+
+        let imdb = "\u{feff}Position,Const,Created,Modified,Description,Title,URL,Title Type,IMDb Rating,Runtime (mins),Year\n\
+            1,tt0144665,2024-01-02,2024-01-02,,La via dei babbuini,https://www.imdb.com/title/tt0144665/,movie,6.8,95,1974\n\
+            2,tt11280740,2024-01-02,2024-01-02,,Severance,https://www.imdb.com/title/tt11280740/,tvSeries,8.7,55,2022\n";
+        let entries = read_export(Service::Imdb, imdb.as_bytes()).unwrap();
+        assert_eq!(entries[0].imdb_code.as_deref(), Some("tt0144665"));
+        assert_eq!(entries[0].year, Some(1974));
+        assert!(!entries[1].is_movie());
+
+        assert!(read_export(Service::Letterboxd, imdb.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn resolves_entries() {
+        let movies = movies();
+        let resolve =
+            |entry: &Entry, movies: &[Movie]| resolve_entry(entry, &TitleMatcher::new(movies));
+        let matched = |entry: &Entry| match resolve(entry, &movies) {
+            Resolution::Matched(movie) => movie.id,
+            _ => None,
+        };
+        assert_eq!(
+            matched(&entry("whatever", None, Some("tt0144665"))),
+            Some(32080)
+        );
+        assert_eq!(
+            matched(&entry("La Via dei Babuini", Some(1975), None)),
+            Some(32080)
+        );
+        assert_eq!(
+            matched(&entry("Dating List", Some(2019), None)),
+            Some(32078),
+            "articles don't matter"
+        );
+        assert!(matches!(
+            resolve(&entry("La via dei babbuini", Some(1990), None), &movies),
+            Resolution::Missing
+        ));
+        assert!(matches!(
+            resolve(&entry("Up", None, None), &movies),
+            Resolution::Missing
+        ));
+
+        let mut remake = movies[0].clone();
+        remake.id = Some(1);
+        remake.year = Some(1975);
+        let movies = vec![movies[0].clone(), remake];
+        assert!(matches!(
+            resolve(&entry("La via dei babbuini", None, None), &movies),
+            Resolution::Ambiguous(found) if found.len() == 2
+        ));
+        assert!(matches!(
+            resolve(&entry("La via dei babbuini", Some(1975), None), &movies),
+            Resolution::Matched(movie) if movie.id == Some(1)
+        ));
+    }
+
+    #[test]
+    fn keeps_imdb_codes_apart() {
+        // trakt's little women (2019) shouldn't turn into the 2018 one
+        let mut older = movies().remove(0);
+        older.title = Some("Little Women".into());
+        older.year = Some(2018);
+        older.imdb_code = Some("tt5182856".into());
+        let newer = entry("Little Women", Some(2019), Some("tt3281548"));
+        assert!(matches!(
+            resolve_entry(&newer, &TitleMatcher::new(&[older.clone()])),
+            Resolution::Missing
+        ));
+
+        older.imdb_code = None;
+        assert!(
+            matches!(
+                resolve_entry(&newer, &TitleMatcher::new(&[older])),
+                Resolution::Matched(_)
+            ),
+            "movies without a code still go by title"
+        );
+    }
+}
@@ -1,2 +1,28 @@
+pub mod artwork;
+pub mod bencode;
+pub mod client;
+pub mod config;
+pub mod date;
+pub mod db;
+pub mod download;
+pub mod filter;
+pub mod imdb;
+pub mod import;
+pub mod library;
+pub mod lists;
+pub mod lookup;
+pub mod mock;
+pub mod nfo;
 pub mod parse;
+pub mod replay;
 pub mod request;
+pub mod search;
+pub mod size;
+pub mod snapshot;
+pub mod stats;
+pub mod store;
+pub mod titles;
+pub mod torrent_client;
+pub mod torrent_file;
+pub mod view;
+pub mod watch;
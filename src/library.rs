@@ -0,0 +1,358 @@
+use crate::download::quality_rank;
+use crate::parse::api::Movie;
+use crate::store::{load_json_or_default, save_json_pretty};
+use crate::titles::TitleMatcher;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+const VIDEO_EXTENSIONS: [&str; 8] = ["mkv", "mp4", "avi", "m4v", "mov", "wmv", "mpg", "ts"];
+
+/// What could be told about a movie from the name of its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedName {
+    pub title: String,
+    pub year: Option<u16>,
+    /// In yts's spelling, e.g. "1080p".
+    pub quality: Option<String>,
+    /// Whether the release is tagged as a yts/yify one.
+    pub yts: bool,
+}
+
+/// The movies found on disk, as of the last scan.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Library {
+    pub scanned: Option<DateTime<Utc>>,
+    pub root: PathBuf,
+    /// The matched files, by movie id.
+    pub owned: BTreeMap<u32, Vec<OwnedFile>>,
+    /// The video files that couldn't be matched to a movie.
+    pub unmatched: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnedFile {
+    pub path: PathBuf,
+    pub quality: Option<String>,
+}
+
+/// An owned movie that has a torrent in a better quality than the best file on disk.
+#[derive(Debug)]
+pub struct Upgrade<'a> {
+    pub movie: &'a Movie,
+    pub owned: Option<String>,
+    pub available: String,
+}
+
+/// Parses release style file names, e.g. `The.Matrix.1999.1080p.BluRay.x264-[YTS.MX].mp4`
+/// or `The Matrix (1999) [720p] [YTS.AG].mkv`. Returns None when there's no title left.
+pub fn parse_file_name(file_name: &str) -> Option<ParsedName> {
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if extension.len() <= 4 && !extension.contains(' ') => stem,
+        _ => file_name,
+    };
+    let words = stem
+        .split(|c: char| c.is_whitespace() || "._-()[]{}".contains(c))
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>();
+
+    // the last year-looking word that isn't the first one, so that "2001 A Space Odyssey 1968" works
+    let year_at = words
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, w)| is_year(w))
+        .map(|(i, _)| i)
+        .next_back();
+    let mut title_end = year_at.unwrap_or(words.len());
+
+    let mut quality = None;
+    let mut yts = false;
+    for (i, word) in words.iter().enumerate() {
+        let lower = word.to_lowercase();
+        let found = match lower.as_str() {
+            "720p" => Some("720p"),
+            "1080p" => Some("1080p"),
+            "2160p" | "4k" | "uhd" => Some("2160p"),
+            "3d" => Some("3D"),
+            _ => None,
+        };
+        if let Some(found) = found {
+            quality.get_or_insert_with(|| found.to_string());
+        }
+        let tag = lower.starts_with("yts") || lower.starts_with("yify");
+        yts |= tag;
+        if tag || (i > 0 && found.is_some()) {
+            title_end = title_end.min(i);
+        }
+    }
+
+    let title = words[..title_end].join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    Some(ParsedName {
+        title,
+        year: year_at.and_then(|i| words[i].parse().ok()),
+        quality,
+        yts,
+    })
+}
+
+fn is_year(word: &str) -> bool {
+    word.len() == 4
+        && (word.starts_with("19") || word.starts_with("20"))
+        && word.chars().all(|c| c.is_ascii_digit())
+}
+
+/// The first imdb code (`tt` and 7 or 8 digits) in the text, e.g. in an .nfo file.
+pub fn find_imdb_code(text: &str) -> Option<String> {
+    text.match_indices("tt").find_map(|(at, _)| {
+        if at > 0 && text.as_bytes()[at - 1].is_ascii_alphanumeric() {
+            return None;
+        }
+        let digits = text[at + 2..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+        (digits.len() == 7 || digits.len() == 8).then(|| format!("tt{}", digits))
+    })
+}
+
+impl Library {
+    /// Loads the library saved at `path`. A missing file means an empty library.
+    pub fn load(path: &Path) -> Result<Library, Box<dyn Error>> {
+        load_json_or_default(path, "a library")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        save_json_pretty(path, self)
+    }
+
+    /// Walks `root` for video files and matches them against the movies, preferring the imdb codes
+    /// from .nfo files next to them (same name, or the only .nfo in the directory) over their names.
+    pub fn scan(root: &Path, movies: &[Movie]) -> Result<Library, Box<dyn Error>> {
+        let matcher = TitleMatcher::new(movies);
+        let find = |name: &ParsedName| matcher.find(&name.title, name.year, None).found();
+        let mut library = Library {
+            scanned: Some(Utc::now()),
+            root: root.to_path_buf(),
+            ..Library::default()
+        };
+        let mut dirs = vec![root.to_path_buf()];
+        // symlinked directories are followed, but each one only once so that loops end
+        let mut visited = HashSet::new();
+
+        while let Some(dir) = dirs.pop() {
+            if !visited.insert(dir.canonicalize().unwrap_or_else(|_| dir.clone())) {
+                continue;
+            }
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if dir == root => return Err(format!("{}: {}", dir.display(), e).into()),
+                Err(e) => {
+                    warn!("skipping {}: {}", dir.display(), e);
+                    continue;
+                }
+            };
+            let mut videos = vec![];
+            let mut nfos = vec![];
+            for entry in entries {
+                let path = match entry {
+                    Ok(entry) => entry.path(),
+                    Err(e) => {
+                        warn!("skipping an entry of {}: {}", dir.display(), e);
+                        continue;
+                    }
+                };
+                let extension = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_lowercase);
+                match extension.as_deref() {
+                    _ if path.is_dir() => dirs.push(path),
+                    Some("nfo") => nfos.push(path),
+                    Some(e) if VIDEO_EXTENSIONS.contains(&e) => videos.push(path),
+                    _ => {}
+                }
+            }
+
+            for video in videos {
+                let nfo = nfos
+                    .iter()
+                    .find(|nfo| nfo.file_stem() == video.file_stem())
+                    .or(if nfos.len() == 1 { nfos.first() } else { None });
+                let from_nfo = nfo
+                    .and_then(|nfo| std::fs::read(nfo).ok())
+                    .and_then(|bytes| find_imdb_code(&String::from_utf8_lossy(&bytes)))
+                    .and_then(|code| matcher.by_imdb(&code));
+
+                // fall back to the directory name for files named e.g. "movie.mkv"
+                let name = video
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(parse_file_name);
+                let dir_name = dir
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(parse_file_name);
+                let movie = from_nfo
+                    .or_else(|| name.as_ref().and_then(find))
+                    .or_else(|| dir_name.as_ref().and_then(find));
+
+                match movie.and_then(|m| m.id) {
+                    Some(id) => library.owned.entry(id).or_default().push(OwnedFile {
+                        quality: name
+                            .and_then(|n| n.quality)
+                            .or_else(|| dir_name.and_then(|n| n.quality)),
+                        path: video,
+                    }),
+                    None => library.unmatched.push(video),
+                }
+            }
+        }
+
+        library.unmatched.sort();
+        Ok(library)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.owned.keys().copied()
+    }
+
+    pub fn contains(&self, movie: &Movie) -> bool {
+        matches!(movie.id, Some(id) if self.owned.contains_key(&id))
+    }
+
+    /// The owned movies that have a torrent in a better quality than the best owned file.
+    pub fn upgrades<'a>(&self, movies: &'a [Movie]) -> Vec<Upgrade<'a>> {
+        movies
+            .iter()
+            .filter_map(|movie| {
+                let files = self.owned.get(&movie.id?)?;
+                let owned = files
+                    .iter()
+                    .filter_map(|f| f.quality.clone())
+                    .max_by_key(|q| quality_rank(Some(q)));
+                let available = movie
+                    .torrents
+                    .iter()
+                    .flatten()
+                    .filter_map(|t| t.quality.clone())
+                    .max_by_key(|q| quality_rank(Some(q)))?;
+                (quality_rank(Some(&available)) > quality_rank(owned.as_deref())).then_some(
+                    Upgrade {
+                        movie,
+                        owned,
+                        available,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::library::{find_imdb_code, parse_file_name, Library, ParsedName};
+    use crate::parse::api::{ListResponse, Movie};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    fn parsed(title: &str, year: Option<u16>, quality: Option<&str>, yts: bool) -> ParsedName {
+        ParsedName {
+            title: title.to_string(),
+            year,
+            quality: quality.map(str::to_string),
+            yts,
+        }
+    }
+
+    #[test]
+    fn parses_file_names() {
+        assert_eq!(
+            parse_file_name("The.Matrix.1999.1080p.BluRay.x264-[YTS.MX].mp4"),
+            Some(parsed("The Matrix", Some(1999), Some("1080p"), true))
+        );
+        assert_eq!(
+            parse_file_name("The Matrix (1999) [720p] [YTS.AG].mkv"),
+            Some(parsed("The Matrix", Some(1999), Some("720p"), true))
+        );
+        assert_eq!(
+            parse_file_name("2001 A Space Odyssey (1968) [2160p].mkv"),
+            Some(parsed(
+                "2001 A Space Odyssey",
+                Some(1968),
+                Some("2160p"),
+                false
+            ))
+        );
+        assert_eq!(
+            parse_file_name("Blade_Runner_2049_2017_4K_YIFY.mkv"),
+            Some(parsed("Blade Runner 2049", Some(2017), Some("2160p"), true))
+        );
+        assert_eq!(
+            parse_file_name("Heat.avi"),
+            Some(parsed("Heat", None, None, false))
+        );
+        assert_eq!(parse_file_name("[YTS.MX].mp4"), None);
+    }
+
+    #[test]
+    fn finds_imdb_codes() {
+        assert_eq!(
+            find_imdb_code("https://www.imdb.com/title/tt0144665/"),
+            Some("tt0144665".to_string())
+        );
+        assert_eq!(
+            find_imdb_code("<uniqueid type=\"imdb\">tt10627720</uniqueid>"),
+            Some("tt10627720".to_string())
+        );
+        assert_eq!(find_imdb_code("attt0144665 tt12"), None);
+    }
+
+    #[test]
+    fn scans() {
+        let root = std::env::temp_dir().join(format!("yts-library-{}", std::process::id()));
+        let nfo_dir = root.join("some dir");
+        std::fs::create_dir_all(&nfo_dir).unwrap();
+        std::fs::write(
+            root.join("The.Dating.List.2019.720p.WEBRip-[YTS.LT].mp4"),
+            "",
+        )
+        .unwrap();
+        std::fs::write(nfo_dir.join("movie.mkv"), "").unwrap();
+        std::fs::write(nfo_dir.join("movie.nfo"), "imdb: tt0144665").unwrap();
+        std::fs::write(root.join("Unknown Movie (2001).mkv"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, nfo_dir.join("loop")).unwrap();
+
+        let movies = movies();
+        let library = Library::scan(&root, &movies).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(library.ids().collect::<Vec<u32>>(), vec![32078, 32080]);
+        assert_eq!(library.unmatched.len(), 1);
+        assert!(library.unmatched[0].ends_with("Unknown Movie (2001).mkv"));
+        assert!(library.contains(&movies[0]));
+
+        let upgrades = library.upgrades(&movies);
+        let upgrades = upgrades
+            .iter()
+            .map(|u| (u.movie.title(), u.owned.clone(), u.available.as_str()))
+            .collect::<Vec<_>>();
+        assert!(upgrades.contains(&(
+            "The Dating List".to_string(),
+            Some("720p".to_string()),
+            "1080p"
+        )));
+    }
+}
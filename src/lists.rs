@@ -0,0 +1,146 @@
+use crate::parse::api::Movie;
+use crate::request::MovieRef;
+use crate::store::{load_json_or_default, save_json_pretty};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+/// A personal list of movies (e.g. a watchlist or the already seen ones),
+/// keeping a snapshot of every movie as it was when it was last looked up.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MovieList {
+    entries: BTreeMap<u32, Entry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Entry {
+    pub added: DateTime<Utc>,
+    pub movie: Movie,
+}
+
+impl MovieList {
+    /// Loads the list saved at `path`. A missing file means an empty list.
+    pub fn load(path: &Path) -> Result<MovieList, Box<dyn Error>> {
+        load_json_or_default(path, "a movie list")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        save_json_pretty(path, self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the movie, or refreshes its snapshot if it's already there.
+    /// Returns false for movies without an id, which can't be kept track of.
+    pub fn add(&mut self, movie: Movie) -> bool {
+        let id = match movie.id {
+            Some(id) => id,
+            None => return false,
+        };
+        match self.entries.get_mut(&id) {
+            Some(entry) => entry.movie = movie,
+            None => {
+                self.entries.insert(
+                    id,
+                    Entry {
+                        added: Utc::now(),
+                        movie,
+                    },
+                );
+            }
+        }
+        true
+    }
+
+    /// Removes the referred movie and returns its entry, if it was there.
+    pub fn remove(&mut self, movie: &MovieRef) -> Option<Entry> {
+        let id = self.find(movie)?.movie.id?;
+        self.entries.remove(&id)
+    }
+
+    pub fn find(&self, movie: &MovieRef) -> Option<&Entry> {
+        self.entries
+            .values()
+            .find(|entry| movie.matches(&entry.movie))
+    }
+
+    pub fn contains(&self, movie: &Movie) -> bool {
+        match movie.id {
+            Some(id) => self.entries.contains_key(&id),
+            None => false,
+        }
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.keys().copied()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// The movie snapshots, oldest addition first.
+    pub fn movies(&self) -> Vec<Movie> {
+        let mut entries = self.entries.values().collect::<Vec<&Entry>>();
+        entries.sort_by_key(|entry| entry.added);
+        entries
+            .into_iter()
+            .map(|entry| entry.movie.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lists::MovieList;
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::request::MovieRef;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    #[test]
+    fn add_find_remove() {
+        let mut list = MovieList::default();
+        for movie in movies() {
+            assert!(list.add(movie));
+        }
+        assert_eq!(list.len(), 2);
+        assert!(list.contains(&movies()[0]));
+
+        // adding again only refreshes the snapshot
+        list.add(movies()[0].clone());
+        assert_eq!(list.len(), 2);
+
+        let imdb = "tt0144665".parse::<MovieRef>().unwrap();
+        assert_eq!(
+            list.find(&imdb).map(|e| e.movie.title()),
+            Some("La via dei babbuini".to_string())
+        );
+        assert!(list.remove(&imdb).is_some());
+        assert!(list.remove(&imdb).is_none());
+        assert_eq!(list.ids().collect::<Vec<u32>>(), vec![32078]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut list = MovieList::default();
+        list.add(movies()[1].clone());
+        let path = std::env::temp_dir().join(format!("yts-list-{}.json", std::process::id()));
+        list.save(&path).unwrap();
+        let loaded = MovieList::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.movies()[0].title(), "The Dating List");
+    }
+}
@@ -0,0 +1,169 @@
+use crate::parse::api::Movie;
+use crate::request::{CacheSettings, DetailsRequest, ListRequest, MovieRef};
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Reads the movies listed in a file, one yts id or imdb code per line. Blank lines and
+/// `#` comments are skipped, as is anything after the first word, so imdb urls work as well.
+/// Every movie is only returned once.
+pub fn read_refs(text: &str) -> Result<Vec<MovieRef>, String> {
+    let mut movies: Vec<MovieRef> = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let word = match line.split(|c: char| c.is_whitespace() || c == ',').next() {
+            Some(word) if !word.is_empty() => word,
+            _ => continue,
+        };
+        let movie = word
+            .parse()
+            .or_else(|e| imdb_code(word).ok_or(e))
+            .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        if !movies.contains(&movie) {
+            movies.push(movie);
+        }
+    }
+    Ok(movies)
+}
+
+/// The imdb code in something like `https://www.imdb.com/title/tt0068646/`.
+fn imdb_code(text: &str) -> Option<MovieRef> {
+    text.match_indices("tt").find_map(|(start, _)| {
+        let digits: String = text[start + 2..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        (!digits.is_empty()).then(|| MovieRef::Imdb(format!("tt{}", digits)))
+    })
+}
+
+/// The movie through `movie_details`, or else, for imdb codes, through a search for the code.
+/// None when yts doesn't have it.
+pub fn resolve(
+    movie: &MovieRef,
+    cache: &CacheSettings,
+    mirror: Option<&str>,
+) -> Result<Option<Movie>, Box<dyn Error>> {
+    let mut details = DetailsRequest::new(movie.clone());
+    details.cache(cache);
+    if let Some(mirror) = mirror {
+        details.mirror(mirror)?;
+    }
+    if let Some(found) = details.execute()? {
+        return Ok(Some(found));
+    }
+
+    let code = match movie {
+        MovieRef::Imdb(code) => code,
+        MovieRef::Id(_) => return Ok(None),
+    };
+    let mut search = ListRequest::new();
+    search.query_term(code.clone()).limit(5).cache(cache);
+    if let Some(mirror) = mirror {
+        search.mirror(mirror)?;
+    }
+    Ok(search
+        .execute()?
+        .data
+        .and_then(|d| d.movies)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|m| m.imdb_code.as_deref() == Some(code.as_str())))
+}
+
+/// Resolves every item with `resolve`, running at most `jobs` at a time.
+/// The results come in the order of the items.
+pub fn resolve_all<T, R, F>(items: &[T], jobs: usize, resolve: F) -> Vec<Result<R, String>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R, Box<dyn Error>> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let item = match items.get(i) {
+                    Some(item) => item,
+                    None => break,
+                };
+                let result = resolve(item).map_err(|e| e.to_string());
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item gets resolved"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lookup::{read_refs, resolve_all};
+    use crate::parse::api::Movie;
+    use crate::request::MovieRef;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn reads_refs() {
+        let refs = read_refs(
+            "# to watch\n\
+             tt0068646\n\
+             \n\
+             3175 the godfather part ii\n\
+             https://www.imdb.com/title/tt0071562/?ref_=fn_al_tt_1\n\
+             tt0068646,The Godfather\n",
+        )
+        .unwrap();
+        assert_eq!(
+            refs,
+            vec![
+                MovieRef::Imdb("tt0068646".into()),
+                MovieRef::Id(3175),
+                MovieRef::Imdb("tt0071562".into()),
+            ]
+        );
+        assert_eq!(
+            read_refs("tt1\nthe godfather").unwrap_err(),
+            "line 2: 'the' is neither a yts id nor an imdb code (e.g. tt0068646)"
+        );
+    }
+
+    #[test]
+    fn resolves_concurrently_in_order() {
+        let movies: Vec<MovieRef> = (1..=20).map(MovieRef::Id).collect();
+        let running = AtomicUsize::new(0);
+        let most = AtomicUsize::new(0);
+
+        let results = resolve_all(&movies, 4, |movie| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            match movie {
+                MovieRef::Id(id) if id % 5 == 0 => Ok(None),
+                MovieRef::Id(13) => Err("boom".into()),
+                MovieRef::Id(id) => Ok(Some(Movie {
+                    id: Some(*id),
+                    ..serde_json::from_str("{}").unwrap()
+                })),
+                MovieRef::Imdb(_) => unreachable!(),
+            }
+        });
+
+        assert!(most.load(Ordering::SeqCst) <= 4);
+        assert_eq!(results.len(), 20);
+        assert_eq!(results[0].as_ref().unwrap().as_ref().unwrap().id, Some(1));
+        assert!(results[4].as_ref().unwrap().is_none());
+        assert_eq!(results[12].as_ref().unwrap_err(), "boom");
+        assert!(results[19].as_ref().unwrap().is_none());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::db::Catalogue;
+use crate::parse::api::{DetailsData, DetailsResponse, ListResponse, Movie};
+use crate::request::{Genre, ListRequest, MovieRef};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Mutex;
+use url::Url;
+
+/// How many movies `movie_suggestions.json` returns, like the real one.
+const SUGGESTIONS: usize = 4;
+
+const PIECE_LENGTH: u64 = 4 * 1024 * 1024;
+
+/// A stand-in for the yts api, serving a set of movies with the same filtering, sorting and
+/// pagination the offline catalogue does. Like the catalogue it can't sort by download or like
+/// count, and answers those with an error.
+pub struct MockApi {
+    catalogue: Mutex<Catalogue>,
+    torrents: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Records {
+    Movies(Vec<Movie>),
+    Movie(Box<Movie>),
+}
+
+impl MockApi {
+    /// Loads every `.json` file in `dir`, each holding either a movie or a list of movies, as the
+    /// api returns them. The .torrent files are read from `dir/torrents/<HASH>.torrent`.
+    /// Torrents that don't have one get a made up file, which is only good for inspecting.
+    /// Torrent urls are pointed at `base`.
+    pub fn load(dir: &Path, base: &Url) -> Result<MockApi, Box<dyn Error>> {
+        let mut movies = vec![];
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        for path in paths {
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let records: Records = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| format!("{} isn't a movie: {}", path.display(), e))?;
+            match records {
+                Records::Movies(list) => movies.extend(list),
+                Records::Movie(movie) => movies.push(*movie),
+            }
+        }
+
+        let mut torrents = HashMap::new();
+        let torrent_dir = dir.join("torrents");
+        if torrent_dir.is_dir() {
+            for entry in std::fs::read_dir(torrent_dir)? {
+                let path = entry?.path();
+                if let Some(hash) = path.file_stem().and_then(|s| s.to_str()) {
+                    torrents.insert(hash.to_uppercase(), std::fs::read(&path)?);
+                }
+            }
+        }
+
+        MockApi::new(movies, torrents, base)
+    }
+
+    /// Serves the given movies and .torrent files (by upper case info-hash).
+    pub fn new(
+        mut movies: Vec<Movie>,
+        mut torrents: HashMap<String, Vec<u8>>,
+        base: &Url,
+    ) -> Result<MockApi, Box<dyn Error>> {
+        for movie in &mut movies {
+            let title = movie.title_long();
+            for torrent in movie.torrents.iter_mut().flatten() {
+                let hash = torrent.hash.clone().unwrap_or_default().to_uppercase();
+                let hash = if torrents.contains_key(&hash) {
+                    hash
+                } else {
+                    let name = format!(
+                        "{} [{}] [YTS.MX]",
+                        title,
+                        torrent.quality.as_deref().unwrap_or_default()
+                    );
+                    let (hash, bytes) = made_up_torrent(&name, torrent.size_bytes.unwrap_or(0));
+                    torrents.insert(hash.clone(), bytes);
+                    hash
+                };
+                torrent.url = Some(base.join(&format!("torrent/download/{}", hash))?);
+                torrent.hash = Some(hash);
+            }
+        }
+
+        let mut catalogue = Catalogue::open_in_memory()?;
+        catalogue.upsert(&movies)?;
+        Ok(MockApi {
+            catalogue: Mutex::new(catalogue),
+            torrents,
+        })
+    }
+
+    /// `list_movies.json`, with the query parameters of a [`ListRequest`].
+    pub fn list(&self, query: &[(String, String)]) -> Result<ListResponse, String> {
+        let mut request = ListRequest::new();
+        request.limit(20);
+        for (key, value) in query {
+            match key.as_str() {
+                "limit" => {
+                    request.limit(parse(key, value)?);
+                    if !(1..=50).contains(&request.limit.unwrap_or(0)) {
+                        return Err("limit should be between 1 and 50".to_string());
+                    }
+                }
+                "page" => {
+                    request.page(parse(key, value)?);
+                }
+                "quality" if value.eq_ignore_ascii_case("all") => {}
+                "quality" => {
+                    request.quality(parse(key, value)?);
+                }
+                "minimum_rating" => {
+                    request.rating(parse(key, value)?);
+                }
+                "query_term" => {
+                    request.query_term(value.to_string());
+                }
+                "genre" => {
+                    request.genre(Genre::lookup(value)?);
+                }
+                "sort_by" => {
+                    request.sort_by(parse(key, value)?);
+                }
+                "order_by" => {
+                    request.order_by(parse(key, value)?);
+                }
+                _ => {}
+            }
+        }
+
+        self.catalogue
+            .lock()
+            .map_err(|e| e.to_string())?
+            .list(&request)
+            .map_err(|e| e.to_string())
+    }
+
+    /// `movie_details.json`, by `movie_id` or `imdb_id`. Unknown movies come back with the id 0.
+    pub fn details(&self, query: &[(String, String)]) -> Result<DetailsResponse, String> {
+        let movie = self.find(query)?.unwrap_or_else(|| Movie {
+            id: Some(0),
+            ..unknown_movie()
+        });
+        Ok(DetailsResponse {
+            status: Some("ok".to_string()),
+            status_message: Some("Query was successful".to_string()),
+            data: Some(DetailsData { movie: Some(movie) }),
+            meta: None,
+        })
+    }
+
+    /// `movie_suggestions.json`: the best rated movies sharing a genre with the given one.
+    pub fn suggestions(&self, query: &[(String, String)]) -> Result<ListResponse, String> {
+        let movie = self.find(query)?.ok_or("no such movie")?;
+        let genres = movie.genres.clone().unwrap_or_default();
+        let mut movies: Vec<Movie> = self
+            .catalogue
+            .lock()
+            .map_err(|e| e.to_string())?
+            .movies()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|other| other.id != movie.id)
+            .filter(|other| other.genres.iter().flatten().any(|g| genres.contains(g)))
+            .collect();
+        movies.sort_by(|a, b| b.rating.unwrap_or(0.0).total_cmp(&a.rating.unwrap_or(0.0)));
+        movies.truncate(SUGGESTIONS);
+        Ok(movies.into())
+    }
+
+    /// The .torrent file with the given info-hash.
+    pub fn torrent(&self, hash: &str) -> Option<&[u8]> {
+        self.torrents.get(&hash.to_uppercase()).map(Vec::as_slice)
+    }
+
+    fn find(&self, query: &[(String, String)]) -> Result<Option<Movie>, String> {
+        let movie: MovieRef = query
+            .iter()
+            .find(|(key, _)| key == "movie_id" || key == "imdb_id")
+            .ok_or("movie_id or imdb_id is required")?
+            .1
+            .parse()?;
+        self.catalogue
+            .lock()
+            .map_err(|e| e.to_string())?
+            .movie(&movie)
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", key, value))
+}
+
+fn unknown_movie() -> Movie {
+    serde_json::from_str("{}").expect("every field is optional")
+}
+
+/// A single file torrent with no pieces, and its info-hash.
+fn made_up_torrent(name: &str, length: u64) -> (String, Vec<u8>) {
+    let info = format!(
+        "d6:lengthi{}e4:name{}:{}12:piece lengthi{}e6:pieces0:e",
+        length,
+        name.len(),
+        name,
+        PIECE_LENGTH
+    );
+    let hash = Sha1::digest(info.as_bytes())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect();
+    let announce = "udp://tracker.opentrackr.org:1337/announce";
+    let torrent = format!("d8:announce{}:{}4:info{}e", announce.len(), announce, info);
+    (hash, torrent.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockApi;
+    use crate::parse::api::ListResponse;
+    use crate::torrent_file::TorrentFile;
+    use std::collections::HashMap;
+    use url::Url;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn api() -> MockApi {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let movies = response.data.unwrap().movies.unwrap();
+        MockApi::new(
+            movies,
+            HashMap::new(),
+            &Url::parse("http://127.0.0.1:8002/").unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn titles(list: ListResponse) -> Vec<String> {
+        list.data
+            .unwrap()
+            .movies
+            .unwrap()
+            .iter()
+            .map(|m| m.title())
+            .collect()
+    }
+
+    #[test]
+    fn lists() {
+        let api = api();
+        assert_eq!(titles(api.list(&[]).unwrap()).len(), 2);
+        assert_eq!(
+            titles(
+                api.list(&query(&[("sort_by", "title"), ("order_by", "asc")]))
+                    .unwrap()
+            ),
+            vec!["La via dei babbuini", "The Dating List"]
+        );
+        assert_eq!(
+            titles(
+                api.list(&query(&[("genre", "action"), ("quality", "1080p")]))
+                    .unwrap()
+            ),
+            vec!["The Dating List"]
+        );
+        let page = api.list(&query(&[("limit", "1"), ("page", "2")])).unwrap();
+        let data = page.data.as_ref().unwrap();
+        assert_eq!((data.movie_count, data.page_number), (Some(2), Some(2)));
+        assert_eq!(data.movies.as_ref().unwrap().len(), 1);
+
+        assert!(api.list(&query(&[("limit", "51")])).is_err());
+        assert_eq!(
+            api.list(&query(&[("genre", "comdy")])).unwrap_err(),
+            "unknown genre 'comdy', did you mean 'comedy'?"
+        );
+    }
+
+    #[test]
+    fn details_and_suggestions() {
+        let api = api();
+        let details = api.details(&query(&[("imdb_id", "tt0144665")])).unwrap();
+        assert_eq!(details.data.unwrap().movie.unwrap().id, Some(32080));
+        let unknown = api.details(&query(&[("movie_id", "1")])).unwrap();
+        assert_eq!(unknown.data.unwrap().movie.unwrap().id, Some(0));
+        assert!(api.details(&[]).is_err());
+
+        // the two fixture movies have no genre in common
+        let suggestions = api.suggestions(&query(&[("movie_id", "32080")])).unwrap();
+        assert!(titles(suggestions).is_empty());
+    }
+
+    #[test]
+    fn serves_verifiable_torrents() {
+        let api = api();
+        let movie = api
+            .details(&query(&[("movie_id", "32080")]))
+            .unwrap()
+            .data
+            .unwrap()
+            .movie
+            .unwrap();
+        let torrent = &movie.torrents.as_ref().unwrap()[0];
+        let url = torrent.url.as_ref().unwrap();
+        assert!(url
+            .as_str()
+            .starts_with("http://127.0.0.1:8002/torrent/download/"));
+
+        let bytes = api.torrent(torrent.hash.as_ref().unwrap()).unwrap();
+        let file = TorrentFile::parse(bytes).unwrap();
+        file.verify(torrent).unwrap();
+        assert!(file.name.starts_with("La via dei babbuini (1974) [720p]"));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::artwork::{ArtKind, ArtSize};
+use crate::parse::api::Movie;
+use std::fmt::Write;
+
+/// The Kodi style `movie.nfo` for the movie, which Jellyfin and Emby read as well.
+/// Fields the movie doesn't have are left out.
+pub fn movie_nfo(movie: &Movie) -> String {
+    let mut xml =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\" ?>\n<movie>\n");
+
+    element(&mut xml, "title", movie.title.as_deref());
+    element(
+        &mut xml,
+        "year",
+        movie.year.map(|y| y.to_string()).as_deref(),
+    );
+    if let Some(rating) = movie.rating.filter(|r| *r > 0.0) {
+        let _ = write!(
+            xml,
+            "  <ratings>\n    <rating name=\"imdb\" max=\"10\" default=\"true\">\n      <value>{:.1}</value>\n    </rating>\n  </ratings>\n",
+            rating
+        );
+    }
+    element(
+        &mut xml,
+        "runtime",
+        movie
+            .runtime
+            .filter(|r| *r > 0)
+            .map(|r| r.to_string())
+            .as_deref(),
+    );
+    for genre in movie.genres.iter().flatten() {
+        element(&mut xml, "genre", Some(genre));
+    }
+    element(&mut xml, "mpaa", movie.mpa_rating.as_deref());
+    element(
+        &mut xml,
+        "plot",
+        non_empty(movie.description_full.as_deref()).or(movie.summary.as_deref()),
+    );
+    element(&mut xml, "outline", movie.summary.as_deref());
+    if let Some(code) = non_empty(movie.imdb_code.as_deref()) {
+        let _ = writeln!(
+            xml,
+            "  <uniqueid type=\"imdb\" default=\"true\">{}</uniqueid>",
+            escape(code)
+        );
+    }
+    if let Some(id) = movie.id.filter(|id| *id > 0) {
+        let _ = writeln!(xml, "  <uniqueid type=\"yts\">{}</uniqueid>", id);
+    }
+    if let Some(code) = non_empty(movie.yt_trailer_code.as_deref()) {
+        let trailer = format!(
+            "plugin://plugin.video.youtube/?action=play_video&videoid={}",
+            code
+        );
+        element(&mut xml, "trailer", Some(&trailer));
+    }
+    if let Some(poster) = movie.artwork(ArtKind::Poster, ArtSize::Large) {
+        let _ = writeln!(
+            xml,
+            "  <thumb aspect=\"poster\">{}</thumb>",
+            escape(poster.as_str())
+        );
+    }
+    if let Some(fanart) = movie.artwork(ArtKind::Fanart, ArtSize::Large) {
+        let _ = writeln!(
+            xml,
+            "  <fanart>\n    <thumb>{}</thumb>\n  </fanart>",
+            escape(fanart.as_str())
+        );
+    }
+
+    xml.push_str("</movie>\n");
+    xml
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+fn element(xml: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = non_empty(value) {
+        let _ = writeln!(xml, "  <{name}>{}</{name}>", escape(value), name = name);
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // not allowed in xml 1.0 at all
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nfo::movie_nfo;
+    use crate::parse::api::{ListResponse, Movie};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movie() -> Movie {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap().remove(0)
+    }
+
+    #[test]
+    fn writes_kodi_nfo() {
+        let mut movie = movie();
+        movie.yt_trailer_code = Some("abc".to_string());
+        movie.title = Some("Babbuini & <Co>".to_string());
+        let nfo = movie_nfo(&movie);
+
+        assert!(nfo.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\" ?>\n<movie>\n"
+        ));
+        assert!(nfo.ends_with("</movie>\n"));
+        assert!(nfo.contains("  <title>Babbuini &amp; &lt;Co&gt;</title>\n"));
+        assert!(nfo.contains("  <year>1974</year>\n"));
+        assert!(nfo.contains("<value>6.8</value>"));
+        assert!(nfo.contains("  <genre>Comedy</genre>\n"));
+        assert!(nfo.contains("  <uniqueid type=\"imdb\" default=\"true\">tt0144665</uniqueid>\n"));
+        assert!(nfo.contains("  <uniqueid type=\"yts\">32080</uniqueid>\n"));
+        assert!(nfo.contains(
+            "  <trailer>plugin://plugin.video.youtube/?action=play_video&amp;videoid=abc</trailer>\n"
+        ));
+        assert!(nfo.contains("<plot>This is a probably underrated brave attempt"));
+        assert!(nfo.contains("<thumb aspect=\"poster\">https://yts.mx/assets/images/movies/"));
+    }
+
+    #[test]
+    fn leaves_out_missing_fields() {
+        let mut movie = movie();
+        movie.rating = Some(0.0);
+        movie.mpa_rating = Some("".to_string());
+        movie.genres = None;
+        let nfo = movie_nfo(&movie);
+        assert!(!nfo.contains("<ratings>"));
+        assert!(!nfo.contains("<mpaa>"));
+        assert!(!nfo.contains("<genre>"));
+    }
+}
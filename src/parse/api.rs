@@ -1,14 +1,16 @@
-use chrono::serde::ts_nanoseconds_option;
+use crate::artwork::{ArtKind, ArtSize};
+use crate::date::parse_uploaded;
+use crate::request::Quality;
+use crate::size::{ByteSize, SizeRange};
+use crate::view::{Extras, ListView};
+use chrono::serde::ts_seconds_option;
 use chrono::{DateTime, Utc};
-use colored::Colorize;
-use hyphenation::{Language, Load, Standard};
-use prettytable::{format, Cell, Row, Table};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt::{self};
-use textwrap::{fill, Options as TextWrapOptions, WordSplitter};
+use url::form_urlencoded::byte_serialize;
 use url::Url;
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct ListResponse {
     pub status: Option<String>,
@@ -18,7 +20,7 @@ pub struct ListResponse {
     pub meta: Option<Meta>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct Data {
     pub movie_count: Option<u64>,
@@ -27,7 +29,22 @@ pub struct Data {
     pub movies: Option<Vec<Movie>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// The response of `movie_details.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DetailsResponse {
+    pub status: Option<String>,
+    pub status_message: Option<String>,
+    pub data: Option<DetailsData>,
+    #[serde(rename = "@meta")]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DetailsData {
+    pub movie: Option<Movie>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct Movie {
     pub id: Option<u32>,
@@ -45,19 +62,33 @@ pub struct Movie {
     pub yt_trailer_code: Option<String>,
     pub language: Option<String>,
     pub mpa_rating: Option<String>,
-    pub background_image: Option<String>,
-    pub background_image_original: Option<String>,
-    pub small_cover_image: Option<String>,
-    pub medium_cover_image: Option<String>,
-    pub large_cover_image: Option<String>,
+    #[serde(default, deserialize_with = "lenient_url")]
+    pub background_image: Option<Url>,
+    #[serde(default, deserialize_with = "lenient_url")]
+    pub background_image_original: Option<Url>,
+    #[serde(default, deserialize_with = "lenient_url")]
+    pub small_cover_image: Option<Url>,
+    #[serde(default, deserialize_with = "lenient_url")]
+    pub medium_cover_image: Option<Url>,
+    #[serde(default, deserialize_with = "lenient_url")]
+    pub large_cover_image: Option<Url>,
     pub state: Option<String>,
     pub torrents: Option<Vec<Torrent>>,
     pub date_uploaded: Option<String>,
-    #[serde(with = "ts_nanoseconds_option")]
+    #[serde(default, with = "ts_seconds_option")]
     pub date_uploaded_unix: Option<DateTime<Utc>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// Image urls are sometimes empty strings, which mean there's no image rather than a broken response.
+fn lenient_url<'de, D>(deserializer: D) -> Result<Option<Url>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let url = Option::<String>::deserialize(deserializer)?;
+    Ok(url.and_then(|url| Url::parse(&url).ok()))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct Torrent {
     pub url: Option<Url>,
@@ -65,19 +96,20 @@ pub struct Torrent {
     pub quality: Option<String>,
     #[serde(rename = "type")]
     pub ty_pe: Option<String>,
+    #[serde(rename = "seeds", alias = "seeders")]
     pub seeders: Option<u32>,
     pub peers: Option<u32>,
     pub size: Option<String>,
     pub size_bytes: Option<u64>,
     pub date_uploaded: Option<String>,
-    #[serde(with = "ts_nanoseconds_option")]
+    #[serde(default, with = "ts_seconds_option")]
     pub date_uploaded_unix: Option<DateTime<Utc>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct Meta {
-    #[serde(with = "ts_nanoseconds_option")]
+    #[serde(default, with = "ts_seconds_option")]
     pub server_time: Option<DateTime<Utc>>,
     pub server_timezone: Option<String>,
     pub api_version: Option<u8>,
@@ -116,7 +148,7 @@ impl Movie {
 
     /// Returns the string representation for the long title (including year). It can be empty.
     pub fn title_long(&self) -> String {
-        self.title_long.to_owned().unwrap_or_else(|| "".to_string())
+        self.title_long.to_owned().unwrap_or_default()
     }
 
     /// Returns the string representation for the yts url. It can be empty.
@@ -157,16 +189,137 @@ impl Movie {
         }
     }
 
+    /// The url of the artwork in the given size, or else in the closest size there is.
+    /// Fanart comes in two sizes: the original for large and a smaller one for the rest.
+    pub fn artwork(&self, kind: ArtKind, size: ArtSize) -> Option<&Url> {
+        let candidates = match (kind, size) {
+            (ArtKind::Poster, ArtSize::Small) => [
+                &self.small_cover_image,
+                &self.medium_cover_image,
+                &self.large_cover_image,
+            ],
+            (ArtKind::Poster, ArtSize::Medium) => [
+                &self.medium_cover_image,
+                &self.large_cover_image,
+                &self.small_cover_image,
+            ],
+            (ArtKind::Poster, ArtSize::Large) => [
+                &self.large_cover_image,
+                &self.medium_cover_image,
+                &self.small_cover_image,
+            ],
+            (ArtKind::Fanart, ArtSize::Large) => [
+                &self.background_image_original,
+                &self.background_image,
+                &None,
+            ],
+            (ArtKind::Fanart, _) => [
+                &self.background_image,
+                &self.background_image_original,
+                &None,
+            ],
+        };
+        candidates.iter().find_map(|url| url.as_ref())
+    }
+
+    /// Whether there's a torrent in the given quality.
+    pub fn has_quality(&self, quality: Quality) -> bool {
+        let quality = quality.to_string();
+        self.torrents
+            .iter()
+            .flatten()
+            .any(|t| t.quality.as_deref() == Some(quality.as_str()))
+    }
+
+    /// The torrents from the smallest to the largest, the ones of unknown size last.
+    pub fn torrents_by_size(&self) -> Vec<&Torrent> {
+        let mut torrents: Vec<&Torrent> = self.torrents.iter().flatten().collect();
+        torrents.sort_by_key(|t| (t.bytes().is_none(), t.bytes()));
+        torrents
+    }
+
+    /// When the movie was uploaded: `date_uploaded_unix`, or else `date_uploaded` read in the
+    /// server's timezone (see [`ListResponse::timezone`]).
+    pub fn uploaded(&self, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+        self.date_uploaded_unix
+            .or_else(|| parse_uploaded(self.date_uploaded.as_deref()?, timezone))
+    }
+
+    /// Drops the torrents whose size is out of the range.
+    pub fn retain_sizes(&mut self, range: &SizeRange) {
+        if let Some(torrents) = self.torrents.as_mut() {
+            torrents.retain(|t| range.contains(t.bytes()));
+        }
+    }
+
+    /// Returns the torrents as `720p web 958.30 MiB, 1080p web 1.47 GiB`, smallest first.
+    pub fn torrent_sizes(&self) -> String {
+        self.torrents_by_size()
+            .iter()
+            .map(|t| t.label())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     /// Returns the string representation for the movie summary. It can be empty.
     pub fn text(&self, description_type: MovieDescription) -> String {
         use MovieDescription::*;
         match description_type {
-            Summary => self.summary.clone().unwrap_or_else(|| "".to_string()),
-            Description => self
-                .description_full
-                .clone()
-                .unwrap_or_else(|| "".to_string()),
-            Synopsis => self.synopsis.clone().unwrap_or_else(|| "".to_string()),
+            Summary => self.summary.clone().unwrap_or_default(),
+            Description => self.description_full.clone().unwrap_or_default(),
+            Synopsis => self.synopsis.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl ListResponse {
+    /// The timezone the `date_uploaded` strings are in.
+    pub fn timezone(&self) -> Option<&str> {
+        self.meta.as_ref()?.server_timezone.as_deref()
+    }
+
+    /// The movies, or none.
+    pub fn movies(&self) -> &[Movie] {
+        self.data
+            .as_ref()
+            .and_then(|d| d.movies.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// A line like `2 movies, 3 torrents, 3.18 GiB`, the total size of every listed torrent.
+    pub fn summary(&self) -> String {
+        let torrents: Vec<&Torrent> = self
+            .movies()
+            .iter()
+            .flat_map(|m| m.torrents.iter().flatten())
+            .collect();
+        let size: ByteSize = torrents.iter().filter_map(|t| t.bytes()).sum();
+        let count = |n: usize, what: &str| match n {
+            1 => format!("1 {}", what),
+            n => format!("{} {}s", n, what),
+        };
+        format!(
+            "{}, {}, {}",
+            count(self.movies().len(), "movie"),
+            count(torrents.len(), "torrent"),
+            size
+        )
+    }
+}
+
+/// Wraps movies that didn't come from a list request (e.g. search hits) so they print the same way.
+impl From<Vec<Movie>> for ListResponse {
+    fn from(movies: Vec<Movie>) -> Self {
+        ListResponse {
+            status: Some("ok".to_string()),
+            status_message: None,
+            data: Some(Data {
+                movie_count: Some(movies.len() as u64),
+                limit: None,
+                page_number: None,
+                movies: Some(movies),
+            }),
+            meta: None,
         }
     }
 }
@@ -178,56 +331,65 @@ pub enum MovieDescription {
     Synopsis,
 }
 
-impl fmt::Display for ListResponse {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut table = Table::new();
-        table.set_format(*format::consts::FORMAT_NO_COLSEP); // NO_BORDER?
-
-        let data = match &self.data {
-            Some(data) => data,
-            None => {
-                writeln!(f, "missing data from response")?;
-                return Ok(());
-            }
-        };
+/// The trackers yts recommends adding to magnet links.
+const TRACKERS: [&str; 8] = [
+    "udp://open.demonii.com:1337/announce",
+    "udp://tracker.openbittorrent.com:80",
+    "udp://tracker.coppersurfer.tk:6969",
+    "udp://glotorrents.pw:6969/announce",
+    "udp://tracker.opentrackr.org:1337/announce",
+    "udp://torrent.gresille.org:80/announce",
+    "udp://p4p.arenabg.com:1337",
+    "udp://tracker.leechers-paradise.org:6969",
+];
 
-        let movies = match &data.movies {
-            Some(movies) if !movies.is_empty() => movies,
-            _ => {
-                writeln!(f, "no movies in response")?;
-                return Ok(());
-            }
-        };
-
-        for movie in movies {
-            let left = format!(
-                "{rating}\n\n{year}\n{genres}\n\n{id}",
-                rating = movie.rating().as_str().green(),
-                year = movie.year().as_str().green(),
-                genres = fill(movie.genres().as_str(), 12),
-                id = movie.id(),
-            );
-            let right = format!(
-                "{title}\n{url}\n{yt}\n{imdb}\n\n{summary}",
-                title = movie.title().as_str().bright_green(),
-                url = movie.url(),
-                yt = movie.youtube(),
-                imdb = movie.imdb(),
-                summary = {
-                    let text = movie.text(MovieDescription::Summary);
-                    let dictionary = Standard::from_embedded(Language::EnglishUS).unwrap();
-                    let options = TextWrapOptions::new(90)
-                        .word_splitter(WordSplitter::Hyphenation(dictionary));
-                    fill(text.as_str(), &options)
-                },
-            );
-            let cells = vec![Cell::new(right.as_str()), Cell::new(left.as_str())];
-            table.add_row(Row::new(cells));
+impl Torrent {
+    /// Returns the quality, type and size, like `1080p web 1.47 GiB`.
+    pub fn label(&self) -> String {
+        let mut label = [self.quality.as_deref(), self.ty_pe.as_deref()]
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(size) = self.bytes() {
+            label.push_str(&format!(" {}", size));
         }
+        label
+    }
 
-        f.write_fmt(format_args!("{}", table))?;
+    /// When the torrent was uploaded, like [`Movie::uploaded`].
+    pub fn uploaded(&self, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+        self.date_uploaded_unix
+            .or_else(|| parse_uploaded(self.date_uploaded.as_deref()?, timezone))
+    }
 
-        Ok(())
+    /// The size from `size_bytes`, or else read from the `size` string.
+    pub fn bytes(&self) -> Option<ByteSize> {
+        self.size_bytes
+            .map(ByteSize)
+            .or_else(|| ByteSize::from_yts(self.size.as_deref()?).ok())
+    }
+
+    /// The magnet link for the torrent, named `name`. None when there's no hash to build it from.
+    pub fn magnet(&self, name: &str) -> Option<String> {
+        let hash = self.hash.as_deref().filter(|hash| !hash.is_empty())?;
+        let mut magnet = format!(
+            "magnet:?xt=urn:btih:{}&dn={}",
+            hash,
+            byte_serialize(name.as_bytes()).collect::<String>()
+        );
+        for tracker in TRACKERS {
+            magnet.push_str("&tr=");
+            magnet.extend(byte_serialize(tracker.as_bytes()));
+        }
+        Some(magnet)
+    }
+}
+
+impl fmt::Display for ListResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ListView::new(self, Extras::default()).fmt(f)
     }
 }
 
@@ -250,6 +412,7 @@ impl fmt::Display for Movie {
 #[cfg(test)]
 mod tests {
     use crate::parse::api::ListResponse;
+    use chrono::{TimeZone, Utc};
     use url::Url;
     static JSON: &str = include_str!("test-data/list.json");
 
@@ -296,7 +459,7 @@ mod tests {
         assert!(movie
             .summary
             .clone()
-            .unwrap_or_else(|| "".to_string())
+            .unwrap_or_default()
             .starts_with("This is a probably underrated brave attempt"));
         let torrent = movie
             .torrents
@@ -315,6 +478,41 @@ mod tests {
             )
         );
         let meta = response.meta.expect("there's a @meta section in the json");
-        assert_eq!(meta.server_time.unwrap().timestamp_nanos(), 1622039993)
+        assert_eq!(meta.server_time.unwrap().timestamp(), 1622039993);
+        assert_eq!(
+            meta.server_time.unwrap().to_rfc3339(),
+            "2021-05-26T14:39:53+00:00"
+        );
+    }
+
+    #[test]
+    fn upload_dates() {
+        let mut response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let timezone = response.timezone().map(String::from);
+        let movie = &mut response.data.as_mut().unwrap().movies.as_mut().unwrap()[0];
+        let uploaded = Utc.ymd(2021, 5, 26).and_hms(13, 39, 26);
+        assert_eq!(movie.date_uploaded_unix, Some(uploaded));
+        assert_eq!(movie.uploaded(timezone.as_deref()), Some(uploaded));
+
+        // the string, in the server's timezone, when there's no timestamp
+        movie.date_uploaded_unix = None;
+        assert_eq!(movie.uploaded(timezone.as_deref()), Some(uploaded));
+        let torrent = &mut movie.torrents.as_mut().unwrap()[0];
+        torrent.date_uploaded_unix = None;
+        assert_eq!(torrent.uploaded(timezone.as_deref()), Some(uploaded));
+    }
+
+    #[test]
+    fn magnet() {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let movie = &response.data.unwrap().movies.unwrap()[0];
+        let torrent = &movie.torrents.as_ref().unwrap()[0];
+        assert_eq!(torrent.seeders, Some(0));
+
+        let magnet = torrent.magnet(&movie.title_long()).unwrap();
+        assert!(magnet.starts_with(
+            "magnet:?xt=urn:btih:673B3BA1335C6D1F5035C086A98676BF6C738276&dn=La+via+dei+babbuini+%281974%29&tr=udp%3A%2F%2F"
+        ));
+        assert_eq!(magnet.matches("&tr=").count(), 8);
     }
 }
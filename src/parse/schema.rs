@@ -0,0 +1,338 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use url::Url;
+
+/// What a json value is expected to be.
+#[derive(Debug)]
+pub enum Kind {
+    String,
+    Integer,
+    Number,
+    Bool,
+    /// A url, or an empty string for none.
+    Url,
+    Array(&'static Kind),
+    Object(&'static [Field]),
+}
+
+/// A field the api is known to send. Null counts as missing.
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+}
+
+const fn required(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+const TORRENT: &[Field] = &[
+    required("url", Kind::Url),
+    required("hash", Kind::String),
+    required("quality", Kind::String),
+    optional("type", Kind::String),
+    optional("seeds", Kind::Integer),
+    optional("peers", Kind::Integer),
+    optional("size", Kind::String),
+    optional("size_bytes", Kind::Integer),
+    optional("date_uploaded", Kind::String),
+    optional("date_uploaded_unix", Kind::Integer),
+];
+
+// title_english, slug, like_count and description_intro aren't part of `Movie`, but they're known
+const MOVIE: &[Field] = &[
+    required("id", Kind::Integer),
+    required("url", Kind::Url),
+    optional("imdb_code", Kind::String),
+    required("title", Kind::String),
+    optional("title_english", Kind::String),
+    optional("title_long", Kind::String),
+    optional("slug", Kind::String),
+    optional("year", Kind::Integer),
+    optional("rating", Kind::Number),
+    optional("runtime", Kind::Integer),
+    optional("genres", Kind::Array(&Kind::String)),
+    optional("like_count", Kind::Integer),
+    optional("summary", Kind::String),
+    optional("description_intro", Kind::String),
+    optional("description_full", Kind::String),
+    optional("synopsis", Kind::String),
+    optional("yt_trailer_code", Kind::String),
+    optional("language", Kind::String),
+    optional("mpa_rating", Kind::String),
+    optional("background_image", Kind::Url),
+    optional("background_image_original", Kind::Url),
+    optional("small_cover_image", Kind::Url),
+    optional("medium_cover_image", Kind::Url),
+    optional("large_cover_image", Kind::Url),
+    optional("state", Kind::String),
+    optional("torrents", Kind::Array(&Kind::Object(TORRENT))),
+    optional("date_uploaded", Kind::String),
+    optional("date_uploaded_unix", Kind::Integer),
+];
+
+const META: &[Field] = &[
+    optional("server_time", Kind::Integer),
+    optional("server_timezone", Kind::String),
+    optional("api_version", Kind::Integer),
+    optional("execution_time", Kind::String),
+];
+
+const LIST_DATA: &[Field] = &[
+    required("movie_count", Kind::Integer),
+    optional("limit", Kind::Integer),
+    optional("page_number", Kind::Integer),
+    // left out when there are no movies
+    optional("movies", Kind::Array(&Kind::Object(MOVIE))),
+];
+
+const DETAILS_DATA: &[Field] = &[required("movie", Kind::Object(MOVIE))];
+
+/// `list_movies.json`
+pub const LIST: Kind = Kind::Object(&[
+    required("status", Kind::String),
+    optional("status_message", Kind::String),
+    required("data", Kind::Object(LIST_DATA)),
+    optional("@meta", Kind::Object(META)),
+]);
+
+/// `movie_details.json`
+pub const DETAILS: Kind = Kind::Object(&[
+    required("status", Kind::String),
+    optional("status_message", Kind::String),
+    required("data", Kind::Object(DETAILS_DATA)),
+    optional("@meta", Kind::Object(META)),
+]);
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Problem {
+    Unknown,
+    Missing,
+    Mismatch { expected: String, found: String },
+}
+
+/// Something about a response that doesn't fit the model, at a json path like `$.data.movies[3].id`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Issue {
+    pub path: String,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Problem::Unknown => write!(f, "{}: unknown field", self.path),
+            Problem::Missing => write!(f, "{}: missing", self.path),
+            Problem::Mismatch { expected, found } => {
+                write!(f, "{}: expected {}, found {}", self.path, expected, found)
+            }
+        }
+    }
+}
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Makes api responses that don't fit the model fail instead of being read as well as they can.
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Everything about the json that doesn't fit the kind.
+pub fn check(json: &Value, kind: &Kind) -> Vec<Issue> {
+    let mut issues = vec![];
+    check_value(json, kind, "$", &mut issues);
+    issues
+}
+
+fn check_value(value: &Value, kind: &Kind, path: &str, issues: &mut Vec<Issue>) {
+    let fits = match (kind, value) {
+        (Kind::String, Value::String(_)) => true,
+        (Kind::Integer, Value::Number(n)) => n.is_i64() || n.is_u64(),
+        (Kind::Number, Value::Number(_)) => true,
+        (Kind::Bool, Value::Bool(_)) => true,
+        (Kind::Url, Value::String(s)) => s.is_empty() || Url::parse(s).is_ok(),
+        (Kind::Array(item), Value::Array(values)) => {
+            for (i, value) in values.iter().enumerate() {
+                check_value(value, item, &format!("{}[{}]", path, i), issues);
+            }
+            true
+        }
+        (Kind::Object(fields), Value::Object(map)) => {
+            for field in fields.iter() {
+                let path = format!("{}.{}", path, field.name);
+                match map.get(field.name) {
+                    None | Some(Value::Null) if field.required => issues.push(Issue {
+                        path,
+                        problem: Problem::Missing,
+                    }),
+                    None | Some(Value::Null) => {}
+                    Some(value) => check_value(value, &field.kind, &path, issues),
+                }
+            }
+            for name in map.keys() {
+                if !fields.iter().any(|field| field.name == name) {
+                    issues.push(Issue {
+                        path: format!("{}.{}", path, name),
+                        problem: Problem::Unknown,
+                    });
+                }
+            }
+            true
+        }
+        _ => false,
+    };
+    if !fits {
+        issues.push(Issue {
+            path: path.to_string(),
+            problem: Problem::Mismatch {
+                expected: kind.to_string(),
+                found: describe(value),
+            },
+        });
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::String => write!(f, "a string"),
+            Kind::Integer => write!(f, "an integer"),
+            Kind::Number => write!(f, "a number"),
+            Kind::Bool => write!(f, "a boolean"),
+            Kind::Url => write!(f, "a url"),
+            Kind::Array(_) => write!(f, "an array"),
+            Kind::Object(_) => write!(f, "an object"),
+        }
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("the boolean {}", b),
+        Value::Number(n) => format!("the number {}", n),
+        Value::String(s) if s.chars().count() > 40 => "a string".to_string(),
+        Value::String(s) => format!("the string {:?}", s),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+/// The issues grouped by where they are in any array element (`$.data.movies[*].id`), with how
+/// many times each came up. Mismatches of the same field count together, showing the first value.
+pub fn drift(issues: &[Issue]) -> Vec<(Issue, usize)> {
+    let mut grouped: BTreeMap<(String, Problem), (Issue, usize)> = BTreeMap::new();
+    for issue in issues {
+        let mut path = String::with_capacity(issue.path.len());
+        let mut in_index = false;
+        for c in issue.path.chars() {
+            match c {
+                '[' => {
+                    in_index = true;
+                    path.push_str("[*");
+                }
+                ']' => {
+                    in_index = false;
+                    path.push(']');
+                }
+                _ if in_index => {}
+                c => path.push(c),
+            }
+        }
+        let key = match &issue.problem {
+            Problem::Mismatch { expected, .. } => Problem::Mismatch {
+                expected: expected.clone(),
+                found: String::new(),
+            },
+            problem => problem.clone(),
+        };
+        let problem = issue.problem.clone();
+        grouped
+            .entry((path.clone(), key))
+            .or_insert((Issue { path, problem }, 0))
+            .1 += 1;
+    }
+    grouped.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::schema::{check, drift, Issue, Problem, DETAILS, LIST};
+    use serde_json::{json, Value};
+    static JSON: &str = include_str!("test-data/list.json");
+
+    #[test]
+    fn fixture_fits() {
+        let json: Value = serde_json::from_str(JSON).unwrap();
+        assert_eq!(check(&json, &LIST), vec![]);
+    }
+
+    #[test]
+    fn finds_drift() {
+        let mut json: Value = serde_json::from_str(JSON).unwrap();
+        let movies = json["data"]["movies"].as_array_mut().unwrap();
+        for movie in movies.iter_mut() {
+            movie["cast"] = json!([]);
+        }
+        movies[0]["rating"] = json!("6.8");
+        movies[1]["torrents"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("hash");
+        movies[1]["id"] = Value::Null;
+
+        let issues = check(&json, &LIST);
+        let messages: Vec<String> = issues.iter().map(Issue::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "$.data.movies[0].rating: expected a number, found the string \"6.8\"",
+                "$.data.movies[0].cast: unknown field",
+                "$.data.movies[1].id: missing",
+                "$.data.movies[1].torrents[0].hash: missing",
+                "$.data.movies[1].cast: unknown field",
+            ]
+        );
+
+        let drift = drift(&issues);
+        assert_eq!(drift.len(), 4);
+        assert!(drift.contains(&(
+            Issue {
+                path: "$.data.movies[*].cast".to_string(),
+                problem: Problem::Unknown
+            },
+            2
+        )));
+    }
+
+    #[test]
+    fn details() {
+        let movie: Value =
+            serde_json::from_str::<Value>(JSON).unwrap()["data"]["movies"][0].clone();
+        let json = json!({"status": "ok", "data": {"movie": movie}});
+        assert_eq!(check(&json, &DETAILS), vec![]);
+        assert_eq!(
+            check(&json!({"status": "ok", "data": {}}), &DETAILS)[0].to_string(),
+            "$.data.movie: missing"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// The environment variable naming the directory api responses get recorded to.
+pub const RECORD_VAR: &str = "YTS_RECORD";
+/// The environment variable naming the directory api responses get replayed from.
+pub const REPLAY_VAR: &str = "YTS_REPLAY";
+
+/// Recorded api responses, for running everything that talks to yts without the network.
+///
+/// Responses are keyed by the normalized url (see [`normalize`]), so a recording made against
+/// one mirror replays for the others and a request replays no matter the order of its parameters.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Fixtures {
+    /// Saves every response into the directory, next to what's already there.
+    Record(PathBuf),
+    /// Serves responses out of the directory and fails the requests it has nothing for.
+    Replay(PathBuf),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    url: String,
+    body: String,
+}
+
+impl Fixtures {
+    /// `YTS_REPLAY` if it's set, or else `YTS_RECORD`, or else None.
+    pub fn from_env() -> Option<Fixtures> {
+        let var = |name| std::env::var_os(name).filter(|dir| !dir.is_empty());
+        var(REPLAY_VAR)
+            .map(|dir| Fixtures::Replay(dir.into()))
+            .or_else(|| var(RECORD_VAR).map(|dir| Fixtures::Record(dir.into())))
+    }
+
+    pub fn dir(&self) -> &Path {
+        match self {
+            Fixtures::Record(dir) | Fixtures::Replay(dir) => dir,
+        }
+    }
+
+    /// The recorded body for the url. Only ever Some when replaying.
+    pub fn replay(&self, url: &Url) -> Result<Option<String>, Box<dyn Error>> {
+        if let Fixtures::Record(_) = self {
+            return Ok(None);
+        }
+        let path = self.path(url);
+        if !path.exists() {
+            return Err(format!(
+                "no recorded response for {} in {}",
+                normalize(url),
+                self.dir().display()
+            )
+            .into());
+        }
+        let fixture: Fixture = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Some(fixture.body))
+    }
+
+    /// Saves the body for the url, when recording.
+    pub fn record(&self, url: &Url, body: &str) -> Result<(), Box<dyn Error>> {
+        if let Fixtures::Replay(_) = self {
+            return Ok(());
+        }
+        let fixture = Fixture {
+            url: normalize(url),
+            body: body.to_string(),
+        };
+        std::fs::create_dir_all(self.dir())?;
+        std::fs::write(self.path(url), serde_json::to_string_pretty(&fixture)?)?;
+        Ok(())
+    }
+
+    fn path(&self, url: &Url) -> PathBuf {
+        let hash: String = Sha1::digest(normalize(url).as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+        self.dir().join(format!("{}.json", hash))
+    }
+}
+
+/// The path and the sorted query of the url, which is what tells two api requests apart.
+pub fn normalize(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+
+    let mut normalized = url.path().trim_end_matches('/').to_string();
+    if !pairs.is_empty() {
+        normalized.push('?');
+        normalized.push_str(
+            &url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(pairs)
+                .finish(),
+        );
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::replay::{normalize, Fixtures};
+    use url::Url;
+
+    #[test]
+    fn normalizes() {
+        let url = |url| Url::parse(url).unwrap();
+        assert_eq!(
+            normalize(&url(
+                "https://yts.mx/api/v2/list_movies.json?page=2&limit=5"
+            )),
+            "/api/v2/list_movies.json?limit=5&page=2"
+        );
+        assert_eq!(
+            normalize(&url(
+                "https://YTS.lt/api/v2/list_movies.json?limit=5&page=2#top"
+            )),
+            normalize(&url(
+                "https://yts.mx/api/v2/list_movies.json?page=2&limit=5"
+            ))
+        );
+        assert_eq!(
+            normalize(&url("http://127.0.0.1:1234/api/v2/movie_details.json/")),
+            "/api/v2/movie_details.json"
+        );
+    }
+
+    #[test]
+    fn records_and_replays() {
+        let dir = std::env::temp_dir().join(format!("yts-replay-{}", std::process::id()));
+        let url = Url::parse("https://yts.mx/api/v2/list_movies.json?limit=1&page=3").unwrap();
+        let other = Url::parse("https://yts.ag/api/v2/list_movies.json?page=3&limit=1").unwrap();
+
+        Fixtures::Record(dir.clone())
+            .record(&url, "{\"status\":\"ok\"}")
+            .unwrap();
+        let replay = Fixtures::Replay(dir.clone());
+        assert_eq!(
+            replay.replay(&other).unwrap().as_deref(),
+            Some("{\"status\":\"ok\"}")
+        );
+        let missing = Url::parse("https://yts.mx/api/v2/list_movies.json?limit=2").unwrap();
+        assert!(replay.replay(&missing).is_err());
+        assert_eq!(Fixtures::Record(dir.clone()).replay(&url).unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,29 +1,68 @@
-use crate::parse::api::ListResponse;
-use cached_path::{Cache, Options as CacheOptions};
+use crate::client;
+use crate::parse::api::{DetailsResponse, ListResponse, Movie};
+use crate::parse::schema::{self, Kind};
+use crate::replay::Fixtures;
+use crate::search::edit_distance;
 use reqwest::blocking::ClientBuilder;
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
 use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::string::ToString;
 use std::time::Duration;
-use strum::{Display, EnumIter, EnumString, EnumVariantNames, IntoStaticStr};
+use strum::{Display, EnumIter, EnumString, EnumVariantNames, IntoEnumIterator, IntoStaticStr};
 use url::Url;
 
 #[derive(Debug, Default)]
 pub struct ListRequest<'a> {
-    limit: Option<u8>,
-    page: Option<u32>,
-    rating: Option<u8>,
-    quality: Option<Quality>,
-    minimum_rating: Option<u8>,
-    query_term: Option<String>,
-    genre: Option<&'a str>,
-    sort_by: Option<Sort>,
-    order_by: Option<Order>,
-    wirth_rt_ratings: Option<bool>,
-    mirror: Option<&'a str>,
+    pub(crate) limit: Option<u8>,
+    pub(crate) page: Option<u32>,
+    pub(crate) quality: Option<Quality>,
+    pub(crate) minimum_rating: Option<u8>,
+    pub(crate) query_term: Option<String>,
+    pub(crate) genre: Option<Genre>,
+    pub(crate) sort_by: Option<Sort>,
+    pub(crate) order_by: Option<Order>,
+    pub(crate) wirth_rt_ratings: Option<bool>,
+    pub(crate) mirror: Option<&'a str>,
+    pub(crate) cache: Option<&'a CacheSettings>,
+}
+
+/// Where and whether api responses get cached.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            enabled: true,
+            dir: std::env::temp_dir().join("yts/"),
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Display, EnumString, EnumVariantNames, EnumIter, IntoStaticStr)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Display,
+    EnumString,
+    EnumVariantNames,
+    EnumIter,
+    IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum Quality {
     #[strum(serialize = "720p")]
     Quality720p,
@@ -77,6 +116,70 @@ pub enum Order {
     Desc,
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Display,
+    EnumString,
+    EnumVariantNames,
+    EnumIter,
+    IntoStaticStr,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[strum(ascii_case_insensitive)]
+pub enum Genre {
+    All,
+    Action,
+    Adventure,
+    Animation,
+    Biography,
+    Comedy,
+    Crime,
+    Documentary,
+    Drama,
+    Family,
+    Fantasy,
+    FilmNoir,
+    GameShow,
+    History,
+    Horror,
+    Music,
+    Musical,
+    Mystery,
+    News,
+    RealityTv,
+    Romance,
+    SciFi,
+    Sport,
+    TalkShow,
+    Thriller,
+    War,
+    Western,
+}
+
+impl Genre {
+    /// The genre with the given name, or an error that suggests the closest one.
+    pub fn lookup(name: &str) -> Result<Genre, String> {
+        name.parse().map_err(|_| match Genre::closest(name) {
+            Some(genre) => format!("unknown genre '{}', did you mean '{}'?", name, genre),
+            None => format!("unknown genre '{}', see `yts genres`", name),
+        })
+    }
+
+    /// The genre whose name is within a couple of typos of `name`.
+    fn closest(name: &str) -> Option<Genre> {
+        let name = name.to_lowercase();
+        Genre::iter()
+            .map(|genre| (edit_distance(&name, genre.into()), genre))
+            .filter(|(distance, _)| *distance <= 2.max(name.len() / 4))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, genre)| genre)
+    }
+}
+
 impl<'a> ListRequest<'a> {
     pub fn new() -> ListRequest<'a> {
         ListRequest::default()
@@ -91,7 +194,7 @@ impl<'a> ListRequest<'a> {
         self
     }
     pub fn rating(&mut self, rating: u8) -> &mut Self {
-        self.rating = Some(rating);
+        self.minimum_rating = Some(rating);
         self
     }
     pub fn quality(&mut self, quality: Quality) -> &mut Self {
@@ -102,7 +205,7 @@ impl<'a> ListRequest<'a> {
         self.query_term = Some(query_term);
         self
     }
-    pub fn genre(&mut self, genre: &'a str) -> &mut Self {
+    pub fn genre(&mut self, genre: Genre) -> &mut Self {
         self.genre = Some(genre);
         self
     }
@@ -121,11 +224,17 @@ impl<'a> ListRequest<'a> {
     /// This will change the domain name or fail
     pub fn mirror(&mut self, mirror: &'a str) -> Result<&mut Self, Box<dyn Error>> {
         let mut u = Url::parse("https://smth.com")?;
-        u.set_host(Some(&mirror))?;
-        self.mirror = Some(&mirror);
+        u.set_host(Some(mirror))?;
+        self.mirror = Some(mirror);
         Ok(self)
     }
 
+    /// Replaces the default cache settings.
+    pub fn cache(&mut self, cache: &'a CacheSettings) -> &mut Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn url(&self) -> Url {
         let mut url = Url::parse("https://yts.mx/api/v2/list_movies.json").unwrap();
 
@@ -146,8 +255,8 @@ impl<'a> ListRequest<'a> {
         if let Some(val) = &self.query_term {
             url.query_pairs_mut().append_pair("query_term", val);
         }
-        if let Some(val) = self.genre {
-            url.query_pairs_mut().append_pair("genre", val);
+        if let Some(val) = &self.genre {
+            url.query_pairs_mut().append_pair("genre", val.into());
         }
         if let Some(val) = &self.sort_by {
             url.query_pairs_mut().append_pair("sort_by", val.into());
@@ -167,7 +276,139 @@ impl<'a> ListRequest<'a> {
     }
 
     pub fn execute(&self) -> Result<ListResponse, Box<dyn Error>> {
-        get_list(&self.url())
+        get_json(&self.execute_raw()?, &schema::LIST, schema::strict())
+    }
+
+    /// The response as it came, without checking it against the model.
+    pub fn execute_raw(&self) -> Result<Value, Box<dyn Error>> {
+        let cache = self.cache.cloned().unwrap_or_default();
+        Ok(serde_json::from_str(&get_text(
+            &self.url(),
+            &cache,
+            "list",
+        )?)?)
+    }
+}
+
+/// A single movie, by its yts id or by its imdb code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MovieRef {
+    Id(u32),
+    Imdb(String),
+}
+
+impl FromStr for MovieRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(id) = s.parse() {
+            return Ok(MovieRef::Id(id));
+        }
+        match s.strip_prefix("tt") {
+            Some(digits) if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(MovieRef::Imdb(s.to_string()))
+            }
+            _ => Err(format!(
+                "'{}' is neither a yts id nor an imdb code (e.g. tt0068646)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for MovieRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MovieRef::Id(id) => write!(f, "{}", id),
+            MovieRef::Imdb(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+impl From<u32> for MovieRef {
+    fn from(id: u32) -> Self {
+        MovieRef::Id(id)
+    }
+}
+
+impl MovieRef {
+    /// Whether the movie is the one referred to.
+    pub fn matches(&self, movie: &Movie) -> bool {
+        match self {
+            MovieRef::Id(id) => movie.id == Some(*id),
+            MovieRef::Imdb(code) => movie.imdb_code.as_deref() == Some(code.as_str()),
+        }
+    }
+}
+
+/// Retrieves the details of a single movie.
+#[derive(Debug)]
+pub struct DetailsRequest<'a> {
+    movie: MovieRef,
+    mirror: Option<&'a str>,
+    cache: Option<&'a CacheSettings>,
+}
+
+impl<'a> DetailsRequest<'a> {
+    pub fn new(movie: MovieRef) -> DetailsRequest<'a> {
+        DetailsRequest {
+            movie,
+            mirror: None,
+            cache: None,
+        }
+    }
+
+    /// This will change the domain name or fail
+    pub fn mirror(&mut self, mirror: &'a str) -> Result<&mut Self, Box<dyn Error>> {
+        let mut u = Url::parse("https://smth.com")?;
+        u.set_host(Some(mirror))?;
+        self.mirror = Some(mirror);
+        Ok(self)
+    }
+
+    /// Replaces the default cache settings.
+    pub fn cache(&mut self, cache: &'a CacheSettings) -> &mut Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn url(&self) -> Url {
+        let mut url = Url::parse("https://yts.mx/api/v2/movie_details.json").unwrap();
+
+        match &self.movie {
+            MovieRef::Id(id) => url
+                .query_pairs_mut()
+                .append_pair("movie_id", &id.to_string()),
+            MovieRef::Imdb(code) => url.query_pairs_mut().append_pair("imdb_id", code),
+        };
+        if let Some(val) = self.mirror {
+            // val is validated in the mirror setter so it's safe at this point
+            url.set_host(Some(val)).unwrap();
+        }
+
+        url
+    }
+
+    /// The response as it came, without checking it against the model.
+    pub fn execute_raw(&self) -> Result<Value, Box<dyn Error>> {
+        let cache = self.cache.cloned().unwrap_or_default();
+        Ok(serde_json::from_str(&get_text(
+            &self.url(),
+            &cache,
+            "details",
+        )?)?)
+    }
+
+    /// The movie, or None if yts doesn't know about it.
+    pub fn execute(&self) -> Result<Option<Movie>, Box<dyn Error>> {
+        let response: DetailsResponse =
+            get_json(&self.execute_raw()?, &schema::DETAILS, schema::strict())?;
+        // unknown movies come back as an empty movie with the id 0
+        Ok(response
+            .data
+            .and_then(|data| data.movie)
+            .filter(|movie| matches!(movie.id, Some(id) if id > 0)))
     }
 }
 
@@ -177,30 +418,209 @@ impl<'a> From<ListRequest<'a>> for Url {
     }
 }
 
-/// The list getter wraps a cache layer around the actual api call.
-/// The cache is stored in the os's temp folder.
-/// Whenever the content has modified it will be fetched again.
-fn get_list(url: &Url) -> Result<ListResponse, Box<dyn std::error::Error>> {
+/// Reads the response into the model. When strict, responses that don't fit the schema fail
+/// with every issue found.
+fn get_json<T: DeserializeOwned>(
+    json: &Value,
+    kind: &Kind,
+    strict: bool,
+) -> Result<T, Box<dyn Error>> {
+    if strict {
+        let issues = schema::check(json, kind);
+        if !issues.is_empty() {
+            let issues: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+            return Err(format!(
+                "the response doesn't fit the schema:\n  {}",
+                issues.join("\n  ")
+            )
+            .into());
+        }
+    }
+    Ok(T::deserialize(json)?)
+}
+
+/// The api getter wraps a cache layer around the actual api call.
+/// The cache is stored in the os's temp folder unless the settings say otherwise.
+/// With YTS_RECORD or YTS_REPLAY set, responses are recorded to or replayed from a directory.
+fn get_text(
+    url: &Url,
+    cache: &CacheSettings,
+    subdir: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let fixtures = Fixtures::from_env();
+    if let Some(json) = fixtures
+        .as_ref()
+        .map(|f| f.replay(url))
+        .transpose()?
+        .flatten()
+    {
+        return Ok(json);
+    }
+
+    let json = if cache.enabled {
+        cached_text(url, cache, subdir)?
+    } else {
+        client::shared()?
+            .get(url, HeaderMap::new())?
+            .error_for_status()?
+            .text()?
+    };
+    if let Some(fixtures) = fixtures {
+        fixtures.record(url, &json)?;
+    }
+
+    Ok(json)
+}
+
+/// The body of the url, out of the cache unless the server says it changed (by its ETag).
+fn cached_text(
+    url: &Url,
+    cache: &CacheSettings,
+    subdir: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = cache.dir.join(subdir);
+    let name: String = Sha1::digest(url.as_str().as_bytes())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect();
+    let body_path = dir.join(format!("{}.json", name));
+    let etag_path = dir.join(format!("{}.etag", name));
+
+    let mut headers = HeaderMap::new();
+    if body_path.exists() {
+        if let Ok(etag) = std::fs::read_to_string(&etag_path) {
+            headers.insert(IF_NONE_MATCH, HeaderValue::from_str(&etag)?);
+        }
+    }
+    let response = client::shared()?.get(url, headers)?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(std::fs::read_to_string(body_path)?);
+    }
+
+    let response = response.error_for_status()?;
+    let etag = response.headers().get(ETAG).cloned();
+    let body = response.text()?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&body_path, &body)?;
+    match etag {
+        Some(etag) => std::fs::write(&etag_path, etag.as_bytes())?,
+        None if etag_path.exists() => std::fs::remove_file(&etag_path)?,
+        None => {}
+    }
+    Ok(body)
+}
+
+/// The http client settings shared by every request this crate makes.
+pub(crate) fn client_builder() -> ClientBuilder {
     let mut header_map = HeaderMap::new();
     header_map.insert("user-agent", HeaderValue::from_static("florinutz/yts"));
 
-    let client_builder = ClientBuilder::new()
+    ClientBuilder::new()
         .timeout(Duration::from_secs(5))
-        .default_headers(header_map);
+        .default_headers(header_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::ListResponse;
+    use crate::parse::schema::LIST;
+    use crate::request::{cached_text, get_json, CacheSettings, DetailsRequest, Genre, MovieRef};
+    use serde_json::{json, Value};
+    use url::Url;
+    static JSON: &str = include_str!("parse/test-data/list.json");
 
-    let cache = Cache::builder()
-        .progress_bar(None)
-        .dir(std::env::temp_dir().join("yts/"))
-        .client_builder(client_builder)
-        .build()?;
+    #[test]
+    fn caches_by_etag() {
+        let mut server = mockito::Server::new();
+        let url = Url::parse(&format!("{}/api/v2/list_movies.json", server.url())).unwrap();
+        let cache = CacheSettings {
+            enabled: true,
+            dir: std::env::temp_dir().join(format!("yts-cache-{}", std::process::id())),
+        };
 
-    let path = cache.cached_path_with_options(
-        url.to_string().as_str(),
-        &CacheOptions::default().subdir("list"),
-    )?;
+        let fresh = server
+            .mock("GET", "/api/v2/list_movies.json")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_body("{\"status\":\"ok\"}")
+            .expect(1)
+            .create();
+        let unchanged = server
+            .mock("GET", "/api/v2/list_movies.json")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create();
+
+        assert_eq!(
+            cached_text(&url, &cache, "list").unwrap(),
+            "{\"status\":\"ok\"}"
+        );
+        assert_eq!(
+            cached_text(&url, &cache, "list").unwrap(),
+            "{\"status\":\"ok\"}"
+        );
+        fresh.assert();
+        unchanged.assert();
+        std::fs::remove_dir_all(&cache.dir).unwrap();
+    }
 
-    let json = std::fs::read_to_string(path)?;
-    let res: ListResponse = serde_json::from_str(json.as_str())?;
+    #[test]
+    fn strict_fails_on_drift() {
+        let mut json: Value = serde_json::from_str(JSON).unwrap();
+        assert!(get_json::<ListResponse>(&json, &LIST, true).is_ok());
 
-    Ok(res)
+        json["data"]["movies"][1]["seeders"] = json!(12);
+        assert!(get_json::<ListResponse>(&json, &LIST, false).is_ok());
+        let e = get_json::<ListResponse>(&json, &LIST, true).unwrap_err();
+        assert!(e
+            .to_string()
+            .ends_with("$.data.movies[1].seeders: unknown field"));
+    }
+
+    #[test]
+    fn genres() {
+        assert_eq!("Sci-Fi".parse(), Ok(Genre::SciFi));
+        assert_eq!(Genre::FilmNoir.to_string(), "film-noir");
+        assert_eq!(Genre::lookup("COMEDY"), Ok(Genre::Comedy));
+        assert_eq!(
+            Genre::lookup("scifi"),
+            Err("unknown genre 'scifi', did you mean 'sci-fi'?".to_string())
+        );
+        assert_eq!(
+            Genre::lookup("documentray"),
+            Err("unknown genre 'documentray', did you mean 'documentary'?".to_string())
+        );
+        assert_eq!(
+            Genre::lookup("cartoons"),
+            Err("unknown genre 'cartoons', see `yts genres`".to_string())
+        );
+    }
+
+    #[test]
+    fn movie_refs() {
+        assert_eq!("10".parse(), Ok(MovieRef::Id(10)));
+        assert_eq!(
+            " tt0068646".parse(),
+            Ok(MovieRef::Imdb("tt0068646".to_string()))
+        );
+        assert!("tt".parse::<MovieRef>().is_err());
+        assert!("godfather".parse::<MovieRef>().is_err());
+    }
+
+    #[test]
+    fn details_url() {
+        assert_eq!(
+            DetailsRequest::new(MovieRef::Id(10)).url().to_string(),
+            "https://yts.mx/api/v2/movie_details.json?movie_id=10"
+        );
+        assert_eq!(
+            DetailsRequest::new(MovieRef::Imdb("tt0068646".into()))
+                .mirror("yts.lt")
+                .unwrap()
+                .url()
+                .to_string(),
+            "https://yts.lt/api/v2/movie_details.json?imdb_id=tt0068646"
+        );
+    }
 }
@@ -0,0 +1,487 @@
+use crate::parse::api::Movie;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+/// BM25 saturation and length normalization parameters.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// Score multiplier applied to terms that only matched a query word by prefix or by typo.
+const LOOSE_MATCH_PENALTY: f32 = 0.5;
+
+/// Ordered as in [`Field::ALL`], the order fields get indexed in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Field {
+    Title,
+    Genres,
+    Summary,
+    Description,
+    Synopsis,
+}
+
+impl Field {
+    const ALL: [Field; 5] = [
+        Field::Title,
+        Field::Genres,
+        Field::Summary,
+        Field::Description,
+        Field::Synopsis,
+    ];
+
+    fn boost(self) -> f32 {
+        match self {
+            Field::Title => 4.0,
+            Field::Genres => 2.0,
+            Field::Summary => 1.0,
+            Field::Description => 1.0,
+            Field::Synopsis => 0.5,
+        }
+    }
+
+    fn text(self, movie: &Movie) -> String {
+        match self {
+            Field::Title => movie.title.clone().unwrap_or_default(),
+            Field::Genres => movie.genres(),
+            Field::Summary => movie.summary.clone().unwrap_or_default(),
+            Field::Description => movie.description_full.clone().unwrap_or_default(),
+            Field::Synopsis => movie.synopsis.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Where a term occurs: the document, the field and the token positions within it.
+#[derive(Debug)]
+struct Posting {
+    doc: usize,
+    field: Field,
+    positions: Vec<u32>,
+}
+
+/// An in-memory full-text index over movies.
+///
+/// Titles, summaries, full descriptions, synopses and genres are indexed and hits are ranked
+/// with BM25, titles weighing the most. Queries are made of words, `"quoted phrases"`
+/// and `prefix*` words. Words that don't occur anywhere are matched against title words
+/// that are a typo or two away, so "godfathr" still finds "The Godfather".
+#[derive(Debug, Default)]
+pub struct Index {
+    movies: Vec<Movie>,
+    /// the postings of every term, sorted by document and field as they're added in that order
+    postings: HashMap<String, Vec<Posting>>,
+    /// token count of every field of every document
+    lengths: Vec<HashMap<Field, u32>>,
+    total_lengths: HashMap<Field, u64>,
+}
+
+/// A search result.
+#[derive(Debug)]
+pub struct Hit<'a> {
+    pub movie: &'a Movie,
+    pub score: f32,
+}
+
+#[derive(Debug, PartialEq)]
+enum Clause {
+    Term(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+impl Index {
+    pub fn new() -> Index {
+        Index::default()
+    }
+
+    /// Number of indexed movies.
+    pub fn len(&self) -> usize {
+        self.movies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movies.is_empty()
+    }
+
+    pub fn add(&mut self, movie: Movie) {
+        let doc = self.movies.len();
+        let mut lengths = HashMap::new();
+
+        for field in Field::ALL.iter().copied() {
+            let tokens = tokenize(&field.text(&movie));
+            lengths.insert(field, tokens.len() as u32);
+            *self.total_lengths.entry(field).or_insert(0) += tokens.len() as u64;
+
+            let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+            for (position, token) in tokens.into_iter().enumerate() {
+                positions.entry(token).or_default().push(position as u32);
+            }
+            for (token, positions) in positions {
+                self.postings.entry(token).or_default().push(Posting {
+                    doc,
+                    field,
+                    positions,
+                });
+            }
+        }
+
+        self.lengths.push(lengths);
+        self.movies.push(movie);
+    }
+
+    /// Returns at most `limit` movies matching `query`, best first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit<'_>> {
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+
+        for clause in parse_query(query) {
+            for (doc, score) in self.score_clause(&clause) {
+                *scores.entry(doc).or_insert(0.0) += score;
+            }
+        }
+
+        let mut hits = scores
+            .into_iter()
+            .map(|(doc, score)| Hit {
+                movie: &self.movies[doc],
+                score,
+            })
+            .collect::<Vec<Hit>>();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.movie.id.cmp(&b.movie.id))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    fn score_clause(&self, clause: &Clause) -> HashMap<usize, f32> {
+        let mut scores = HashMap::new();
+
+        match clause {
+            Clause::Term(term) if self.postings.contains_key(term) => {
+                self.add_term_scores(term, 1.0, &mut scores);
+            }
+            Clause::Term(term) => {
+                for (candidate, distance) in self.fuzzy_title_terms(term) {
+                    let weight = LOOSE_MATCH_PENALTY / distance as f32;
+                    self.add_term_scores(candidate, weight, &mut scores);
+                }
+            }
+            Clause::Prefix(prefix) => {
+                for term in self
+                    .postings
+                    .keys()
+                    .filter(|t| t.starts_with(prefix.as_str()))
+                {
+                    let weight = if term == prefix {
+                        1.0
+                    } else {
+                        LOOSE_MATCH_PENALTY
+                    };
+                    self.add_term_scores(term, weight, &mut scores);
+                }
+            }
+            Clause::Phrase(terms) => {
+                let idf = terms.iter().map(|term| self.idf(term)).sum::<f32>();
+                for (doc, field, count) in self.phrase_matches(terms) {
+                    let score = self.bm25(idf, doc, field, count);
+                    *scores.entry(doc).or_insert(0.0) += score;
+                }
+            }
+        }
+
+        scores
+    }
+
+    fn add_term_scores(&self, term: &str, weight: f32, scores: &mut HashMap<usize, f32>) {
+        let idf = self.idf(term);
+        for posting in self.postings.get(term).into_iter().flatten() {
+            let score = self.bm25(idf, posting.doc, posting.field, posting.positions.len());
+            *scores.entry(posting.doc).or_insert(0.0) += weight * score;
+        }
+    }
+
+    /// Inverse document frequency of a term.
+    fn idf(&self, term: &str) -> f32 {
+        let mut docs = self
+            .postings
+            .get(term)
+            .into_iter()
+            .flatten()
+            .map(|p| p.doc)
+            .collect::<Vec<usize>>();
+        // postings are added document by document, so duplicates are adjacent
+        docs.dedup();
+
+        let documents = self.movies.len() as f32;
+        let containing = docs.len() as f32;
+        (1.0 + (documents - containing + 0.5) / (containing + 0.5)).ln()
+    }
+
+    fn bm25(&self, idf: f32, doc: usize, field: Field, frequency: usize) -> f32 {
+        let documents = self.movies.len() as f32;
+        let length = *self.lengths[doc].get(&field).unwrap_or(&0) as f32;
+        let average = *self.total_lengths.get(&field).unwrap_or(&0) as f32 / documents;
+        let frequency = frequency as f32;
+        let normalization = if average > 0.0 {
+            1.0 - B + B * length / average
+        } else {
+            1.0
+        };
+
+        field.boost() * idf * frequency * (K1 + 1.0) / (frequency + K1 * normalization)
+    }
+
+    /// Every (document, field, occurrences) in which the terms appear next to each other.
+    fn phrase_matches(&self, terms: &[String]) -> Vec<(usize, Field, usize)> {
+        let first = match terms.first().and_then(|t| self.postings.get(t)) {
+            Some(postings) => postings,
+            None => return vec![],
+        };
+
+        first
+            .iter()
+            .filter_map(|posting| {
+                let count = posting
+                    .positions
+                    .iter()
+                    .filter(|&&start| {
+                        terms.iter().enumerate().skip(1).all(|(offset, term)| {
+                            self.positions(term, posting.doc, posting.field)
+                                .is_some_and(|p| p.binary_search(&(start + offset as u32)).is_ok())
+                        })
+                    })
+                    .count();
+                (count > 0).then_some((posting.doc, posting.field, count))
+            })
+            .collect()
+    }
+
+    /// Where the term occurs in the field of the document, in ascending order.
+    fn positions(&self, term: &str, doc: usize, field: Field) -> Option<&Vec<u32>> {
+        let postings = self.postings.get(term)?;
+        postings
+            .binary_search_by_key(&(doc, field), |p| (p.doc, p.field))
+            .ok()
+            .map(|i| &postings[i].positions)
+    }
+
+    /// Title words within a small edit distance from `term`, together with that distance.
+    fn fuzzy_title_terms(&self, term: &str) -> Vec<(&String, usize)> {
+        let max_distance = match term.chars().count() {
+            0..=3 => return vec![],
+            4..=6 => 1,
+            _ => 2,
+        };
+
+        self.postings
+            .iter()
+            .filter(|(_, postings)| postings.iter().any(|p| p.field == Field::Title))
+            .filter_map(|(candidate, _)| {
+                let distance = edit_distance(term, candidate);
+                (distance <= max_distance).then_some((candidate, distance))
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<Movie> for Index {
+    fn from_iter<I: IntoIterator<Item = Movie>>(movies: I) -> Self {
+        let mut index = Index::new();
+        index.extend(movies);
+        index
+    }
+}
+
+impl Extend<Movie> for Index {
+    fn extend<I: IntoIterator<Item = Movie>>(&mut self, movies: I) {
+        for movie in movies {
+            self.add(movie);
+        }
+    }
+}
+
+/// Lowercase alphanumeric words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = vec![];
+
+    for (i, part) in query.split('"').enumerate() {
+        // odd parts were between quotes
+        if i % 2 == 1 {
+            let terms = tokenize(part);
+            match terms.len() {
+                0 => {}
+                1 => clauses.push(Clause::Term(terms[0].clone())),
+                _ => clauses.push(Clause::Phrase(terms)),
+            }
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            let prefix = word.ends_with('*');
+            for token in tokenize(word) {
+                clauses.push(if prefix {
+                    Clause::Prefix(token)
+                } else {
+                    Clause::Term(token)
+                });
+            }
+        }
+    }
+
+    clauses
+}
+
+/// The number of single character insertions, deletions, substitutions
+/// and adjacent transpositions needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::search::{edit_distance, parse_query, Clause, Index};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movie(id: u32, title: &str, summary: &str, genres: &[&str]) -> Movie {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "title": title,
+            "summary": summary,
+            "genres": genres,
+        }))
+        .unwrap()
+    }
+
+    fn index() -> Index {
+        vec![
+            movie(
+                1,
+                "The Godfather",
+                "The aging patriarch of an organized crime dynasty transfers control to his son.",
+                &["Crime", "Drama"],
+            ),
+            movie(
+                2,
+                "The Godfather Part II",
+                "The early life and career of Vito Corleone in 1920s New York City.",
+                &["Crime", "Drama"],
+            ),
+            movie(
+                3,
+                "Crime Story",
+                "A son of a crime family goes straight.",
+                &["Drama"],
+            ),
+            movie(
+                4,
+                "Space Odyssey",
+                "Humanity finds a mysterious artifact buried beneath the lunar surface.",
+                &["Sci-Fi"],
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn ids(hits: Vec<crate::search::Hit>) -> Vec<u32> {
+        hits.iter().map(|hit| hit.movie.id.unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_queries() {
+        assert_eq!(
+            parse_query(r#"crime "new york" god*"#),
+            vec![
+                Clause::Term("crime".into()),
+                Clause::Phrase(vec!["new".into(), "york".into()]),
+                Clause::Prefix("god".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_title_matches_first() {
+        let index = index();
+        let hits = index.search("crime", 10);
+        assert_eq!(hits.first().unwrap().movie.id, Some(3));
+        assert_eq!(hits.len(), 3);
+    }
+
+    #[test]
+    fn phrases() {
+        assert_eq!(ids(index().search(r#""new york""#, 10)), vec![2]);
+        assert!(index().search(r#""york new""#, 10).is_empty());
+
+        let mut many = index();
+        many.extend((5..500).map(|id| movie(id, "New Life", "Far from york.", &["Drama"])));
+        assert_eq!(ids(many.search(r#""new york""#, 10)), vec![2]);
+        assert_eq!(many.search(r#""new life""#, 1000).len(), 495);
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(ids(index().search("odys*", 10)), vec![4]);
+        assert_eq!(ids(index().search("godf*", 10)), vec![1, 2]);
+    }
+
+    #[test]
+    fn fuzzy_titles() {
+        assert_eq!(ids(index().search("godfathr", 10)), vec![1, 2]);
+        assert_eq!(ids(index().search("odyssee", 10)), vec![4]);
+        assert!(index().search("zzzzzzzz", 10).is_empty());
+    }
+
+    #[test]
+    fn genres() {
+        assert_eq!(ids(index().search("sci-fi", 10)), vec![4]);
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("godfathr", "godfather"), 1);
+        assert_eq!(edit_distance("cirme", "crime"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn indexes_api_movies() {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let index = response
+            .data
+            .unwrap()
+            .movies
+            .unwrap()
+            .into_iter()
+            .collect::<Index>();
+        assert_eq!(index.len(), 2);
+        let hits = index.search("Tavernier", 10);
+        assert_eq!(hits.first().unwrap().movie.title(), "La via dei babbuini");
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::str::FromStr;
+
+const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// A number of bytes.
+///
+/// Parses `1234`, `700MB`, `1.5 GB` (powers of 1000) and `2GiB`, `700 mib` (powers of 1024),
+/// and displays in powers of 1024, e.g. `1.47 GiB`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+
+    /// Reads the sizes yts shows next to its torrents, like `958.3 MB`. Those are in powers
+    /// of 1024 despite the unit, so `958.3 MB` is 958.3 MiB.
+    pub fn from_yts(s: &str) -> Result<ByteSize, String> {
+        parse(s, true)
+    }
+
+    /// How far the actual size can be from one yts shows, which is rounded: half of its last
+    /// digit, e.g. 0.05 MiB for `958.3 MB`.
+    pub fn yts_precision(s: &str) -> Result<ByteSize, String> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let decimals = number.split_once('.').map_or(0, |(_, d)| d.len()) as i32;
+        let unit = parse(&format!("1{}", unit), true)?;
+        Ok(ByteSize(
+            (unit.bytes() as f64 * 0.5 * 10f64.powi(-decimals)).ceil() as u64,
+        ))
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s, false)
+    }
+}
+
+fn parse(s: &str, binary: bool) -> Result<ByteSize, String> {
+    let invalid = || format!("invalid size '{}'", s);
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+
+    let unit = unit.trim().to_ascii_lowercase();
+    let (power, iec) = match unit.as_str() {
+        "" | "b" => (0, false),
+        "k" | "kb" => (1, false),
+        "m" | "mb" => (2, false),
+        "g" | "gb" => (3, false),
+        "t" | "tb" => (4, false),
+        "kib" => (1, true),
+        "mib" => (2, true),
+        "gib" => (3, true),
+        "tib" => (4, true),
+        _ => return Err(invalid()),
+    };
+    let base: f64 = if iec || binary { 1024.0 } else { 1000.0 };
+    Ok(ByteSize((number * base.powi(power)).round() as u64))
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{:.2} {}", value, UNITS[unit])
+        }
+    }
+}
+
+impl Sum for ByteSize {
+    fn sum<I: Iterator<Item = ByteSize>>(iter: I) -> Self {
+        ByteSize(iter.map(ByteSize::bytes).sum())
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+/// Either a number of bytes or a string like `"2GiB"`.
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Size {
+            Bytes(u64),
+            Text(String),
+        }
+        match Size::deserialize(deserializer)? {
+            Size::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Size::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Inclusive bounds on a size, either of which can be left open.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SizeRange {
+    pub min: Option<ByteSize>,
+    pub max: Option<ByteSize>,
+}
+
+impl SizeRange {
+    pub fn is_open(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Whether the size is within the bounds. Unknown sizes only fit an open range.
+    pub fn contains(&self, size: Option<ByteSize>) -> bool {
+        match size {
+            Some(size) => {
+                self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+            }
+            None => self.is_open(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::size::{ByteSize, SizeRange};
+
+    #[test]
+    fn parses() {
+        assert_eq!("1234".parse(), Ok(ByteSize(1234)));
+        assert_eq!("700MB".parse(), Ok(ByteSize(700_000_000)));
+        assert_eq!("1.5 gb".parse(), Ok(ByteSize(1_500_000_000)));
+        assert_eq!("2GiB".parse(), Ok(ByteSize(2 * 1024 * 1024 * 1024)));
+        assert_eq!("512 KiB".parse(), Ok(ByteSize(512 * 1024)));
+        assert!("2 GX".parse::<ByteSize>().is_err());
+        assert!("GB".parse::<ByteSize>().is_err());
+        assert!("-1GB".parse::<ByteSize>().is_err());
+
+        // the fixture's torrents come with both
+        let mib = |size: ByteSize| size.0 >> 20;
+        assert_eq!(
+            mib(ByteSize::from_yts("958.3 MB").unwrap()),
+            1004850381 >> 20
+        );
+        assert_eq!(
+            mib(ByteSize::from_yts("1.47 GB").unwrap()),
+            1578400481 >> 20
+        );
+        assert_eq!(ByteSize::yts_precision("958.3 MB"), Ok(ByteSize(52429)));
+        assert_eq!(ByteSize::yts_precision("1.47 GB"), Ok(ByteSize(5368710)));
+        assert_eq!(ByteSize::yts_precision("512 B"), Ok(ByteSize(1)));
+    }
+
+    #[test]
+    fn displays() {
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(1578400481).to_string(), "1.47 GiB");
+        assert_eq!(ByteSize(1536 * 1024).to_string(), "1.50 MiB");
+        let size: ByteSize = ByteSize(1578400481).to_string().parse().unwrap();
+        assert_eq!(size.to_string(), "1.47 GiB");
+    }
+
+    #[test]
+    fn ranges() {
+        let range = SizeRange {
+            min: Some(ByteSize(10)),
+            max: Some(ByteSize(20)),
+        };
+        assert!(range.contains(Some(ByteSize(10))));
+        assert!(range.contains(Some(ByteSize(20))));
+        assert!(!range.contains(Some(ByteSize(21))));
+        assert!(!range.contains(None));
+        assert!(SizeRange::default().contains(None));
+    }
+}
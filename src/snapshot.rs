@@ -0,0 +1,321 @@
+use crate::parse::api::{Movie, Torrent};
+use crate::store::{load_json, save_json};
+use chrono::{DateTime, Utc};
+use prettytable::{format, Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// The version of the snapshot format, bumped whenever old snapshots can't be read anymore.
+const VERSION: u32 = 1;
+
+/// The movies of a list or of the catalogue as they were at some point, to compare against later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    pub version: u32,
+    pub taken: DateTime<Utc>,
+    /// Snapshots from before scopes were recorded are taken for pages.
+    #[serde(default)]
+    pub scope: Scope,
+    pub movies: Vec<Movie>,
+}
+
+/// What a snapshot covers, which decides whether a movie missing from a newer one was removed.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// The whole catalogue, so a movie that's gone was removed.
+    Catalogue,
+    /// A page of a list or a query, which movies leave as newer ones come in.
+    #[default]
+    Page,
+}
+
+/// Something that differs between two snapshots.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    NewMovie {
+        movie: Movie,
+    },
+    RemovedMovie {
+        movie: Movie,
+    },
+    /// A torrent (usually a new quality) for a movie both snapshots have.
+    NewTorrent {
+        movie: Movie,
+        torrent: Box<Torrent>,
+    },
+    RemovedTorrent {
+        movie: Movie,
+        torrent: Box<Torrent>,
+    },
+    RatingChanged {
+        movie: Movie,
+        old: Option<f32>,
+        new: Option<f32>,
+    },
+}
+
+impl Snapshot {
+    pub fn new(movies: Vec<Movie>, scope: Scope) -> Snapshot {
+        Snapshot {
+            version: VERSION,
+            taken: Utc::now(),
+            scope,
+            movies,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+        let snapshot: Snapshot = load_json(path, "a snapshot")?;
+        if snapshot.version > VERSION {
+            return Err(format!(
+                "{} is a version {} snapshot, only up to {} can be read",
+                path.display(),
+                snapshot.version,
+                VERSION
+            )
+            .into());
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        save_json(path, self)
+    }
+
+    /// What changed from this snapshot to the newer one. Removed movies are only reported when
+    /// both cover the whole catalogue, see [`Snapshot::shows_removals`].
+    pub fn diff(&self, newer: &Snapshot) -> Vec<Change> {
+        diff(&self.movies, &newer.movies, self.shows_removals(newer))
+    }
+
+    /// Whether a movie missing from the newer snapshot means it was removed, rather than it
+    /// having dropped off the page.
+    pub fn shows_removals(&self, newer: &Snapshot) -> bool {
+        self.scope == Scope::Catalogue && newer.scope == Scope::Catalogue
+    }
+}
+
+/// The changes from the old movies to the new ones: movies and torrents that came and went
+/// and changed ratings, in the order of the new movies, with the removed movies last if
+/// `removals` is set. Movies are matched by id and torrents by info-hash; the ones without
+/// either are left out.
+pub fn diff(old: &[Movie], new: &[Movie], removals: bool) -> Vec<Change> {
+    let old_by_id: BTreeMap<u32, &Movie> = old.iter().filter_map(|m| Some((m.id?, m))).collect();
+    let new_ids: HashSet<u32> = new.iter().filter_map(|m| m.id).collect();
+    let mut changes = vec![];
+
+    for movie in new {
+        let before = match movie.id {
+            Some(id) => old_by_id.get(&id),
+            None => continue,
+        };
+        let before = match before {
+            Some(before) => before,
+            None => {
+                changes.push(Change::NewMovie {
+                    movie: movie.clone(),
+                });
+                continue;
+            }
+        };
+
+        let old_hashes = hashes(before);
+        let new_hashes = hashes(movie);
+        for (hash, torrent) in &new_hashes {
+            if !old_hashes.contains_key(hash) {
+                changes.push(Change::NewTorrent {
+                    movie: movie.clone(),
+                    torrent: Box::new((*torrent).clone()),
+                });
+            }
+        }
+        for (hash, torrent) in &old_hashes {
+            if !new_hashes.contains_key(hash) {
+                changes.push(Change::RemovedTorrent {
+                    movie: movie.clone(),
+                    torrent: Box::new((*torrent).clone()),
+                });
+            }
+        }
+        if before.rating != movie.rating {
+            changes.push(Change::RatingChanged {
+                movie: movie.clone(),
+                old: before.rating,
+                new: movie.rating,
+            });
+        }
+    }
+
+    for movie in old.iter().filter(|_| removals) {
+        if matches!(movie.id, Some(id) if !new_ids.contains(&id)) {
+            changes.push(Change::RemovedMovie {
+                movie: movie.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+fn hashes(movie: &Movie) -> BTreeMap<String, &Torrent> {
+    movie
+        .torrents
+        .iter()
+        .flatten()
+        .filter_map(|t| Some((t.hash.as_deref()?.to_uppercase(), t)))
+        .collect()
+}
+
+impl Change {
+    pub fn movie(&self) -> &Movie {
+        match self {
+            Change::NewMovie { movie }
+            | Change::RemovedMovie { movie }
+            | Change::NewTorrent { movie, .. }
+            | Change::RemovedTorrent { movie, .. }
+            | Change::RatingChanged { movie, .. } => movie,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::NewMovie { .. } => "new movie",
+            Change::RemovedMovie { .. } => "removed movie",
+            Change::NewTorrent { .. } => "new torrent",
+            Change::RemovedTorrent { .. } => "removed torrent",
+            Change::RatingChanged { .. } => "rating",
+        }
+    }
+
+    /// What exactly changed, e.g. the torrent or the ratings.
+    pub fn detail(&self) -> String {
+        let rating = |r: &Option<f32>| r.map(|r| r.to_string()).unwrap_or_else(|| "-".into());
+        match self {
+            Change::NewMovie { movie } | Change::RemovedMovie { movie } => movie.torrent_sizes(),
+            Change::NewTorrent { torrent, .. } | Change::RemovedTorrent { torrent, .. } => {
+                torrent.label()
+            }
+            Change::RatingChanged { old, new, .. } => format!("{} -> {}", rating(old), rating(new)),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {}",
+            self.kind(),
+            self.movie().title_long(),
+            self.detail()
+        )
+    }
+}
+
+/// The changes as a table of kind, movie and detail.
+pub fn changes_table(changes: &[Change]) -> Table {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    table.set_titles(Row::new(vec![
+        Cell::new("CHANGE"),
+        Cell::new("ID"),
+        Cell::new("MOVIE"),
+        Cell::new("DETAIL"),
+    ]));
+    for change in changes {
+        table.add_row(Row::new(vec![
+            Cell::new(change.kind()),
+            Cell::new(&change.movie().id()),
+            Cell::new(&change.movie().title_long()),
+            Cell::new(&change.detail()),
+        ]));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::snapshot::{diff, Change, Scope, Snapshot};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    #[test]
+    fn diffs() {
+        let old = movies();
+        let mut new = movies();
+        assert!(diff(&old, &new, true).is_empty());
+
+        // babbuini comes back under another id, the dating list swaps its 720p for a 2160p and
+        // gets a better rating
+        new.remove(0);
+        let torrents = new[0].torrents.as_mut().unwrap();
+        let mut uhd = torrents.remove(0);
+        uhd.hash = Some("ABCD".into());
+        uhd.quality = Some("2160p".into());
+        torrents.push(uhd);
+        new[0].rating = Some(7.5);
+        let mut added = movies().remove(0);
+        added.id = Some(1);
+        new.insert(0, added);
+
+        let changes: Vec<String> = diff(&old, &new, true)
+            .iter()
+            .map(Change::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "new movie: La via dei babbuini (1974) 720p web 958.30 MiB",
+                "new torrent: The Dating List (2019) 2160p web 809.91 MiB",
+                "removed torrent: The Dating List (2019) 720p web 809.91 MiB",
+                "rating: The Dating List (2019) 0 -> 7.5",
+                "removed movie: La via dei babbuini (1974) 720p web 958.30 MiB",
+            ]
+        );
+        assert_eq!(
+            diff(&old, &new, false).len(),
+            4,
+            "no removed movies for pages"
+        );
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = std::env::temp_dir().join(format!("yts-snapshot-{}.json", std::process::id()));
+        Snapshot::new(movies(), Scope::Catalogue)
+            .save(&path)
+            .unwrap();
+        let snapshot = Snapshot::load(&path).unwrap();
+        assert_eq!(snapshot.movies.len(), 2);
+        assert!(snapshot
+            .diff(&Snapshot::new(movies(), Scope::Catalogue))
+            .is_empty());
+        assert!(snapshot.shows_removals(&Snapshot::new(vec![], Scope::Catalogue)));
+        assert!(!snapshot.shows_removals(&Snapshot::new(vec![], Scope::Page)));
+
+        std::fs::write(
+            &path,
+            "{\"version\": 1, \"taken\": \"2024-01-01T00:00:00Z\", \"movies\": []}",
+        )
+        .unwrap();
+        assert_eq!(Snapshot::load(&path).unwrap().scope, Scope::Page);
+
+        std::fs::write(
+            &path,
+            "{\"version\": 2, \"taken\": \"2024-01-01T00:00:00Z\", \"movies\": []}",
+        )
+        .unwrap();
+        assert!(Snapshot::load(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
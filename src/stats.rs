@@ -0,0 +1,286 @@
+use crate::parse::api::Movie;
+use crate::size::ByteSize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+
+/// Seeder counts the torrents get grouped by, as the lowest count of each group.
+const SEEDER_GROUPS: [(u32, &str); 4] = [(0, "0"), (1, "1-9"), (10, "10-99"), (100, "100+")];
+
+/// What a set of movies looks like: how they spread over genres, years and ratings,
+/// which qualities they come in and how well their torrents are seeded.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub movies: usize,
+    pub torrents: usize,
+    /// Movies per genre.
+    pub genres: BTreeMap<String, usize>,
+    /// How many years the movies get counted together by, 10 for decades.
+    pub year_span: u16,
+    /// Movies per span of years, by its first year.
+    pub years: BTreeMap<u16, usize>,
+    /// Movies per whole rating, 0 to 9 (a 10 counts as 9).
+    pub ratings: BTreeMap<u8, usize>,
+    pub qualities: BTreeMap<String, QualityStats>,
+    /// Torrents per group of seeder counts, see [`SEEDER_GROUPS`].
+    pub seeders: BTreeMap<String, usize>,
+    pub median_seeders: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct QualityStats {
+    /// Movies with at least one torrent in the quality.
+    pub titles: usize,
+    /// The part of all the movies those are, from 0 to 1.
+    pub share: f64,
+    pub torrents: usize,
+    /// The average size of the torrents whose size is known.
+    pub average_size: Option<ByteSize>,
+}
+
+impl Stats {
+    /// The stats of the movies, counting them by `year_span` years (at least 1).
+    pub fn new(movies: &[Movie], year_span: u16) -> Stats {
+        let year_span = year_span.max(1);
+        let mut stats = Stats {
+            movies: movies.len(),
+            year_span,
+            ..Stats::default()
+        };
+        for (_, name) in SEEDER_GROUPS {
+            stats.seeders.insert(name.to_string(), 0);
+        }
+        let mut sizes: BTreeMap<String, Vec<ByteSize>> = BTreeMap::new();
+        let mut seeders = vec![];
+
+        for movie in movies {
+            for genre in movie.genres.iter().flatten() {
+                *stats.genres.entry(genre.clone()).or_insert(0) += 1;
+            }
+            if let Some(year) = movie.year {
+                *stats.years.entry(year - year % year_span).or_insert(0) += 1;
+            }
+            if let Some(rating) = movie.rating {
+                *stats.ratings.entry((rating as u8).min(9)).or_insert(0) += 1;
+            }
+
+            let mut qualities: Vec<&str> = vec![];
+            for torrent in movie.torrents.iter().flatten() {
+                stats.torrents += 1;
+                let quality = torrent.quality.as_deref().unwrap_or("unknown");
+                if !qualities.contains(&quality) {
+                    qualities.push(quality);
+                }
+                let entry = stats.qualities.entry(quality.to_string()).or_default();
+                entry.torrents += 1;
+                sizes
+                    .entry(quality.to_string())
+                    .or_default()
+                    .extend(torrent.bytes());
+
+                let count = torrent.seeders.unwrap_or(0);
+                seeders.push(count);
+                let (_, group) = SEEDER_GROUPS
+                    .iter()
+                    .rev()
+                    .find(|(min, _)| count >= *min)
+                    .unwrap();
+                *stats.seeders.get_mut(*group).unwrap() += 1;
+            }
+            for quality in qualities {
+                stats.qualities.get_mut(quality).unwrap().titles += 1;
+            }
+        }
+
+        for (quality, entry) in stats.qualities.iter_mut() {
+            entry.share = entry.titles as f64 / stats.movies as f64;
+            let sizes = &sizes[quality];
+            if !sizes.is_empty() {
+                let total: ByteSize = sizes.iter().copied().sum();
+                entry.average_size = Some(ByteSize(total.bytes() / sizes.len() as u64));
+            }
+        }
+        seeders.sort_unstable();
+        stats.median_seeders = seeders.get(seeders.len() / 2).copied();
+        stats
+    }
+
+    /// Every number as a `section,key,metric,value` row, e.g. `quality,2160p,share,0.25`.
+    /// Spans of years are keyed by their first year, in a `decade` section or else a `years` one.
+    pub fn write_csv<W: Write>(&self, out: W) -> Result<(), Box<dyn Error>> {
+        let mut csv = csv::Writer::from_writer(out);
+        csv.write_record(["section", "key", "metric", "value"])?;
+        csv.write_record(["total", "", "movies", &self.movies.to_string()])?;
+        csv.write_record(["total", "", "torrents", &self.torrents.to_string()])?;
+        for (genre, count) in &self.genres {
+            csv.write_record(["genre", genre, "movies", &count.to_string()])?;
+        }
+        for (year, count) in &self.years {
+            let section = if self.year_span == 10 {
+                "decade"
+            } else {
+                "years"
+            };
+            csv.write_record([section, &year.to_string(), "movies", &count.to_string()])?;
+        }
+        for (rating, count) in &self.ratings {
+            csv.write_record(["rating", &rating.to_string(), "movies", &count.to_string()])?;
+        }
+        for (quality, entry) in &self.qualities {
+            csv.write_record(["quality", quality, "titles", &entry.titles.to_string()])?;
+            csv.write_record(["quality", quality, "share", &format!("{:.4}", entry.share)])?;
+            csv.write_record(["quality", quality, "torrents", &entry.torrents.to_string()])?;
+            if let Some(size) = entry.average_size {
+                csv.write_record([
+                    "quality",
+                    quality,
+                    "average_size",
+                    &size.bytes().to_string(),
+                ])?;
+            }
+        }
+        for (group, count) in &self.seeders {
+            csv.write_record(["seeders", group, "torrents", &count.to_string()])?;
+        }
+        if let Some(median) = self.median_seeders {
+            csv.write_record(["seeders", "", "median", &median.to_string()])?;
+        }
+        csv.flush()?;
+        Ok(())
+    }
+
+    /// The span of years starting at `year`, e.g. `1970s`, `1974` or `1970-1974`.
+    fn years_label(&self, year: u16) -> String {
+        match self.year_span {
+            1 => year.to_string(),
+            10 => format!("{}s", year),
+            span => format!("{}-{}", year, year + span - 1),
+        }
+    }
+}
+
+/// A bar as long as the count is from the biggest one, at most 40 characters.
+fn bar(count: usize, max: usize) -> String {
+    "#".repeat((count * 40).div_ceil(max.max(1)))
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} movies, {} torrents", self.movies, self.torrents)?;
+
+        writeln!(f, "\nGenres")?;
+        let mut genres: Vec<(&String, &usize)> = self.genres.iter().collect();
+        genres.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (genre, count) in genres {
+            writeln!(f, "  {:<12} {:>6}", genre, count)?;
+        }
+
+        writeln!(
+            f,
+            "\n{}",
+            if self.year_span == 10 {
+                "Decades"
+            } else {
+                "Years"
+            }
+        )?;
+        let max = self.years.values().copied().max().unwrap_or(0);
+        for (year, count) in &self.years {
+            let label = self.years_label(*year);
+            writeln!(f, "  {:<12} {:>6} {}", label, count, bar(*count, max))?;
+        }
+
+        writeln!(f, "\nRatings")?;
+        let max = self.ratings.values().copied().max().unwrap_or(0);
+        for (rating, count) in &self.ratings {
+            let range = format!("{}-{}", rating, rating + 1);
+            writeln!(f, "  {:<12} {:>6} {}", range, count, bar(*count, max))?;
+        }
+
+        writeln!(f, "\nQualities")?;
+        for (quality, entry) in &self.qualities {
+            writeln!(
+                f,
+                "  {:<12} {:>6} titles ({:>5.1}%) {:>6} torrents, {} on average",
+                quality,
+                entry.titles,
+                entry.share * 100.0,
+                entry.torrents,
+                entry
+                    .average_size
+                    .map(|size| size.to_string())
+                    .unwrap_or_else(|| "unknown".into())
+            )?;
+        }
+
+        writeln!(f, "\nSeeders")?;
+        let max = self.seeders.values().copied().max().unwrap_or(0);
+        for (_, group) in SEEDER_GROUPS {
+            let count = self.seeders.get(group).copied().unwrap_or(0);
+            writeln!(f, "  {:<12} {:>6} {}", group, count, bar(count, max))?;
+        }
+        if let Some(median) = self.median_seeders {
+            writeln!(f, "  median       {:>6}", median)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::ListResponse;
+    use crate::size::ByteSize;
+    use crate::stats::Stats;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn stats() -> Stats {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        Stats::new(response.movies(), 10)
+    }
+
+    #[test]
+    fn counts() {
+        let stats = stats();
+        assert_eq!((stats.movies, stats.torrents), (2, 3));
+        assert_eq!(stats.genres["Action"], 1);
+        assert_eq!(stats.years.keys().collect::<Vec<_>>(), vec![&1970, &2010]);
+        assert_eq!(stats.ratings[&6], 1);
+
+        let hd = &stats.qualities["720p"];
+        assert_eq!((hd.titles, hd.share, hd.torrents), (2, 1.0, 2));
+        assert_eq!(
+            hd.average_size,
+            Some(ByteSize((1004850381 + 849252188) / 2))
+        );
+        assert_eq!(stats.qualities["1080p"].share, 0.5);
+        assert_eq!(
+            stats.seeders.values().sum::<usize>(),
+            stats.torrents,
+            "every torrent is in a seeder group"
+        );
+    }
+
+    #[test]
+    fn writes_csv() {
+        let mut out = vec![];
+        stats().write_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.starts_with("section,key,metric,value\ntotal,,movies,2\n"));
+        assert!(csv.contains("\nquality,1080p,share,0.5000\n"));
+        assert!(csv.contains("\ndecade,1970,movies,1\n"));
+    }
+
+    #[test]
+    fn counts_by_any_span_of_years() {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let by_year = Stats::new(response.movies(), 1);
+        assert_eq!(by_year.years.keys().collect::<Vec<_>>(), vec![&1974, &2019]);
+        assert!(by_year.to_string().contains("\nYears\n  1974 "));
+
+        let by_five = Stats::new(response.movies(), 5);
+        assert_eq!(by_five.years.keys().collect::<Vec<_>>(), vec![&1970, &2015]);
+        assert!(by_five.to_string().contains("  2015-2019 "));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A path next to `path` for writing to before renaming into place. It's hidden and unique to
+/// the process and the call, so concurrent writers don't get in each other's way.
+pub fn temporary_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Writes to a temporary file next to the path and renames it into place, so that readers never
+/// see half a file.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let temporary = temporary_path(path);
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temporary);
+        e.into()
+    })
+}
+
+/// Reads the json at `path`, saying that it isn't `what` (e.g. "a snapshot") when it doesn't parse.
+pub fn load_json<T: DeserializeOwned>(path: &Path, what: &str) -> Result<T, Box<dyn Error>> {
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("{} isn't {}: {}", path.display(), what, e).into())
+}
+
+/// Like [`load_json`], but a missing file means the default, e.g. an empty list.
+pub fn load_json_or_default<T: DeserializeOwned + Default>(
+    path: &Path,
+    what: &str,
+) -> Result<T, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    load_json(path, what)
+}
+
+/// Saves the value as json, creating the directory and replacing the file atomically.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    save(path, &serde_json::to_vec(value)?)
+}
+
+/// Like [`save_json`], but indented, for files people read and edit.
+pub fn save_json_pretty<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    save(path, &serde_json::to_vec_pretty(value)?)
+}
+
+fn save(path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_atomically(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{load_json, load_json_or_default, save_json_pretty};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn saves_and_loads_json() {
+        let dir = std::env::temp_dir().join(format!("yts-store-{}", std::process::id()));
+        let path = dir.join("nested").join("values.json");
+        let loaded: BTreeMap<String, u32> = load_json_or_default(&path, "a map").unwrap();
+        assert!(loaded.is_empty());
+        assert!(load_json::<BTreeMap<String, u32>>(&path, "a map").is_err());
+
+        let values: BTreeMap<String, u32> = [("one".to_string(), 1)].into();
+        save_json_pretty(&path, &values).unwrap();
+        assert_eq!(
+            load_json::<BTreeMap<String, u32>>(&path, "a map").unwrap(),
+            values
+        );
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        std::fs::write(&path, "[]").unwrap();
+        let error = load_json::<BTreeMap<String, u32>>(&path, "a map").unwrap_err();
+        assert!(error.to_string().contains("values.json isn't a map"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
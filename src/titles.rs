@@ -0,0 +1,189 @@
+use crate::parse::api::Movie;
+use crate::search::edit_distance;
+use std::collections::HashMap;
+
+/// Lower case words without punctuation and without a leading article, for comparing titles.
+pub fn normalize(title: &str) -> String {
+    let words = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    match words.split_first() {
+        Some((first, rest)) if !rest.is_empty() && ["the", "a", "an"].contains(&first.as_str()) => {
+            rest.join(" ")
+        }
+        _ => words.join(" "),
+    }
+}
+
+/// What a title was matched to.
+#[derive(Debug)]
+pub enum TitleMatch<'a> {
+    Found(&'a Movie),
+    /// Several movies fit the title equally well.
+    Ambiguous(Vec<&'a Movie>),
+    Missing,
+}
+
+impl<'a> TitleMatch<'a> {
+    /// The movie, if exactly one was found.
+    pub fn found(self) -> Option<&'a Movie> {
+        match self {
+            TitleMatch::Found(movie) => Some(movie),
+            _ => None,
+        }
+    }
+}
+
+/// Looks movies up by imdb code or by title and year, for telling which movie a file name or an
+/// entry of some other site's list is about.
+pub struct TitleMatcher<'a> {
+    by_imdb: HashMap<&'a str, &'a Movie>,
+    by_title: HashMap<String, Vec<&'a Movie>>,
+}
+
+impl<'a> TitleMatcher<'a> {
+    pub fn new(movies: &'a [Movie]) -> TitleMatcher<'a> {
+        let mut matcher = TitleMatcher {
+            by_imdb: HashMap::new(),
+            by_title: HashMap::new(),
+        };
+        for movie in movies {
+            if let Some(code) = movie.imdb_code.as_deref() {
+                matcher.by_imdb.insert(code, movie);
+            }
+            matcher
+                .by_title
+                .entry(normalize(&movie.title()))
+                .or_default()
+                .push(movie);
+        }
+        matcher
+    }
+
+    pub fn by_imdb(&self, code: &str) -> Option<&'a Movie> {
+        self.by_imdb.get(code).copied()
+    }
+
+    /// The movie with the title and year. Titles may be a typo or two apart (one every six
+    /// characters) and years one apart, as release years differ between countries and databases,
+    /// and the closest title and year win. Movies with another imdb code than `imdb_code` never
+    /// match, as they're a different movie of the same name.
+    pub fn find(&self, title: &str, year: Option<u16>, imdb_code: Option<&str>) -> TitleMatch<'a> {
+        let title = normalize(title);
+        let tolerance = title.chars().count() / 6;
+        let length = title.chars().count();
+        let fits = |movie: &Movie| match (imdb_code, movie.imdb_code.as_deref()) {
+            (Some(code), Some(other)) => code == other,
+            _ => true,
+        };
+        let years_apart = |movie: &Movie| match (year, movie.year) {
+            (Some(a), Some(b)) => a.abs_diff(b),
+            _ => 0,
+        };
+
+        // an exact title can't be beaten, so the fuzzy search is only for when there's none
+        let exact = self
+            .by_title
+            .get(&title)
+            .into_iter()
+            .flatten()
+            .map(|movie| (0, *movie))
+            .collect::<Vec<_>>();
+        let candidates = if exact
+            .iter()
+            .any(|(_, movie)| fits(movie) && years_apart(movie) <= 1)
+        {
+            exact
+        } else {
+            self.by_title
+                .iter()
+                .filter(|(other, _)| other.chars().count().abs_diff(length) <= tolerance)
+                .filter_map(|(other, movies)| {
+                    let distance = edit_distance(&title, other);
+                    (distance <= tolerance).then_some((distance, movies))
+                })
+                .flat_map(|(distance, movies)| movies.iter().map(move |movie| (distance, *movie)))
+                .collect()
+        };
+
+        let mut best: Vec<&'a Movie> = vec![];
+        let mut best_score = (usize::MAX, u16::MAX);
+        for (distance, movie) in candidates {
+            if !fits(movie) || years_apart(movie) > 1 {
+                continue;
+            }
+            let score = (distance, years_apart(movie));
+            if score < best_score {
+                best_score = score;
+                best.clear();
+            }
+            if score == best_score {
+                best.push(movie);
+            }
+        }
+        // the fuzzy candidates come out of a map, so ties get a stable order
+        best.sort_by_key(|movie| movie.id);
+
+        match best.len() {
+            0 => TitleMatch::Missing,
+            1 => TitleMatch::Found(best[0]),
+            _ => TitleMatch::Ambiguous(best),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::titles::{normalize, TitleMatch, TitleMatcher};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    #[test]
+    fn normalizes() {
+        assert_eq!(normalize("The Dating-List!"), "dating list");
+        assert_eq!(normalize("A"), "a");
+        assert_eq!(normalize("An  Other  Movie"), "other movie");
+    }
+
+    #[test]
+    fn finds_titles() {
+        let movies = movies();
+        let matcher = TitleMatcher::new(&movies);
+        let found = |title, year| matcher.find(title, year, None).found().and_then(|m| m.id);
+
+        assert_eq!(found("Dating List", Some(2019)), Some(32078));
+        assert_eq!(found("La Via dei Babuini", Some(1975)), Some(32080));
+        assert_eq!(found("La via dei babbuini", Some(1990)), None);
+        assert_eq!(found("Up", None), None);
+        assert!(matches!(
+            matcher.find("The Dating List", None, Some("tt0000001")),
+            TitleMatch::Missing
+        ));
+        assert_eq!(matcher.by_imdb("tt0144665").and_then(|m| m.id), Some(32080));
+
+        let mut remake = movies[0].clone();
+        remake.id = Some(1);
+        remake.year = Some(1975);
+        let movies = vec![movies[0].clone(), remake];
+        let matcher = TitleMatcher::new(&movies);
+        assert!(matches!(
+            matcher.find("La via dei babbuini", None, None),
+            TitleMatch::Ambiguous(found) if found.len() == 2
+        ));
+        assert_eq!(
+            matcher
+                .find("La via dei babbuini", Some(1975), None)
+                .found()
+                .and_then(|m| m.id),
+            Some(1)
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use crate::request::client_builder;
+use reqwest::blocking::{multipart, Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use std::cell::RefCell;
+use std::error::Error;
+use url::Url;
+
+/// Something that downloads torrents, e.g. the client running on a seedbox.
+pub trait TorrentClient {
+    fn add(&self, source: &Source, options: &AddOptions) -> Result<(), Box<dyn Error>>;
+}
+
+/// What gets handed to a client.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Magnet(String),
+    /// The contents of a .torrent file.
+    File {
+        name: String,
+        bytes: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AddOptions {
+    pub category: Option<String>,
+    pub save_path: Option<String>,
+    pub paused: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientKind {
+    Qbittorrent,
+    Transmission,
+}
+
+/// A client from the config file:
+///
+/// ```toml
+/// [clients.qbit]
+/// kind = "qbittorrent"
+/// url = "http://seedbox:8080"
+/// username = "admin"
+/// password = "adminadmin"
+/// category = "movies"
+/// ```
+///
+/// The url of a transmission client is the one of its rpc endpoint, e.g. `http://seedbox:9091/transmission/rpc`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientSettings {
+    pub kind: ClientKind,
+    pub url: Url,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Default category (a label for transmission).
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Default download directory.
+    #[serde(default)]
+    pub save_path: Option<String>,
+    /// Whether torrents get added paused by default.
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl ClientSettings {
+    pub fn client(&self) -> Result<Box<dyn TorrentClient>, Box<dyn Error>> {
+        let username = self.username.clone();
+        let password = self.password.clone();
+        Ok(match self.kind {
+            ClientKind::Qbittorrent => {
+                Box::new(QBittorrent::new(self.url.clone(), username, password)?)
+            }
+            ClientKind::Transmission => {
+                Box::new(Transmission::new(self.url.clone(), username, password)?)
+            }
+        })
+    }
+
+    /// The configured defaults for adding torrents.
+    pub fn options(&self) -> AddOptions {
+        AddOptions {
+            category: self.category.clone(),
+            save_path: self.save_path.clone(),
+            paused: self.paused,
+        }
+    }
+}
+
+/// qBittorrent, through its Web API v2.
+pub struct QBittorrent {
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+    http: Client,
+}
+
+impl QBittorrent {
+    pub fn new(
+        mut url: Url,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<QBittorrent, Box<dyn Error>> {
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        Ok(QBittorrent {
+            url,
+            username,
+            password,
+            http: client_builder().build()?,
+        })
+    }
+
+    /// Logs in and returns the session cookie. Without credentials there's no login,
+    /// for setups that don't require authentication for some clients (e.g. localhost).
+    fn login(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let username = match &self.username {
+            Some(username) => username,
+            None => return Ok(None),
+        };
+        let response = self
+            .http
+            .post(self.url.join("api/v2/auth/login")?)
+            .form(&[
+                ("username", username.as_str()),
+                ("password", self.password.as_deref().unwrap_or_default()),
+            ])
+            .send()?
+            .error_for_status()?;
+        let cookie = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next())
+            .find(|cookie| cookie.starts_with("SID="))
+            .map(str::to_string);
+
+        if response.text()?.trim() != "Ok." {
+            return Err("qbittorrent refused the credentials".into());
+        }
+        Ok(cookie)
+    }
+}
+
+impl TorrentClient for QBittorrent {
+    fn add(&self, source: &Source, options: &AddOptions) -> Result<(), Box<dyn Error>> {
+        let cookie = self.login()?;
+        let paused = options.paused.to_string();
+
+        let mut form = match source {
+            Source::Magnet(magnet) => multipart::Form::new().text("urls", magnet.clone()),
+            Source::File { name, bytes } => multipart::Form::new().part(
+                "torrents",
+                multipart::Part::bytes(bytes.clone())
+                    .file_name(name.clone())
+                    .mime_str("application/x-bittorrent")?,
+            ),
+        };
+        // `stopped` replaced `paused` in qbittorrent 5
+        form = form.text("paused", paused.clone()).text("stopped", paused);
+        if let Some(category) = &options.category {
+            form = form.text("category", category.clone());
+        }
+        if let Some(save_path) = &options.save_path {
+            form = form.text("savepath", save_path.clone());
+        }
+
+        let mut request = self
+            .http
+            .post(self.url.join("api/v2/torrents/add")?)
+            .multipart(form);
+        if let Some(cookie) = cookie {
+            request = request.header("cookie", cookie);
+        }
+        let response = request.send()?;
+        if response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+            return Err("qbittorrent says the torrent is not valid".into());
+        }
+        let body = response.error_for_status()?.text()?;
+        if body.trim() == "Fails." {
+            return Err("qbittorrent didn't add the torrent".into());
+        }
+        Ok(())
+    }
+}
+
+/// Transmission, through its json rpc.
+pub struct Transmission {
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+    http: Client,
+    session_id: RefCell<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: String,
+}
+
+impl Transmission {
+    pub fn new(
+        url: Url,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Transmission, Box<dyn Error>> {
+        Ok(Transmission {
+            url,
+            username,
+            password,
+            http: client_builder().build()?,
+            session_id: RefCell::new(None),
+        })
+    }
+
+    fn request(&self, body: &serde_json::Value) -> RequestBuilder {
+        let mut request = self.http.post(self.url.clone()).json(body);
+        if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+        if let Some(session_id) = self.session_id.borrow().as_ref() {
+            request = request.header("X-Transmission-Session-Id", session_id);
+        }
+        request
+    }
+
+    /// Sends the call, getting a new session id first if the current one is missing or expired.
+    fn call(&self, body: &serde_json::Value) -> Result<Response, Box<dyn Error>> {
+        let response = self.request(body).send()?;
+        if response.status() != StatusCode::CONFLICT {
+            return Ok(response);
+        }
+        let session_id = response
+            .headers()
+            .get("X-Transmission-Session-Id")
+            .and_then(|value| value.to_str().ok())
+            .ok_or("transmission didn't hand out a session id")?;
+        self.session_id.replace(Some(session_id.to_string()));
+        Ok(self.request(body).send()?)
+    }
+}
+
+impl TorrentClient for Transmission {
+    fn add(&self, source: &Source, options: &AddOptions) -> Result<(), Box<dyn Error>> {
+        let mut arguments = json!({ "paused": options.paused });
+        match source {
+            Source::Magnet(magnet) => arguments["filename"] = json!(magnet),
+            Source::File { bytes, .. } => arguments["metainfo"] = json!(base64::encode(bytes)),
+        }
+        if let Some(category) = &options.category {
+            arguments["labels"] = json!([category]);
+        }
+        if let Some(save_path) = &options.save_path {
+            arguments["download-dir"] = json!(save_path);
+        }
+
+        let response: RpcResponse = self
+            .call(&json!({ "method": "torrent-add", "arguments": arguments }))?
+            .error_for_status()?
+            .json()?;
+        if response.result != "success" {
+            return Err(format!("transmission didn't add the torrent: {}", response.result).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::torrent_client::{AddOptions, QBittorrent, Source, TorrentClient, Transmission};
+    use mockito::Matcher;
+    use url::Url;
+
+    const MAGNET: &str = "magnet:?xt=urn:btih:673B3BA1335C6D1F5035C086A98676BF6C738276";
+
+    fn options() -> AddOptions {
+        AddOptions {
+            category: Some("movies".to_string()),
+            save_path: Some("/data/movies".to_string()),
+            paused: true,
+        }
+    }
+
+    #[test]
+    fn qbittorrent() {
+        let mut server = mockito::Server::new();
+        let login = server
+            .mock("POST", "/qbit/api/v2/auth/login")
+            .match_body("username=admin&password=secret")
+            .with_header("set-cookie", "SID=s3ss10n; HttpOnly; path=/")
+            .with_body("Ok.")
+            .create();
+        let add = server
+            .mock("POST", "/qbit/api/v2/torrents/add")
+            .match_header("cookie", "SID=s3ss10n")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(MAGNET.replace('?', r"\?")),
+                Matcher::Regex("name=\"category\"\r\n\r\nmovies".to_string()),
+                Matcher::Regex("name=\"savepath\"\r\n\r\n/data/movies".to_string()),
+                Matcher::Regex("name=\"paused\"\r\n\r\ntrue".to_string()),
+            ]))
+            .with_body("Ok.")
+            .create();
+
+        let client = QBittorrent::new(
+            Url::parse(&format!("{}/qbit", server.url())).unwrap(),
+            Some("admin".to_string()),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+        client
+            .add(&Source::Magnet(MAGNET.to_string()), &options())
+            .unwrap();
+
+        login.assert();
+        add.assert();
+    }
+
+    #[test]
+    fn qbittorrent_errors() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/api/v2/auth/login")
+            .with_body("Fails.")
+            .create();
+        let client = QBittorrent::new(
+            Url::parse(&server.url()).unwrap(),
+            Some("admin".to_string()),
+            Some("wrong".to_string()),
+        )
+        .unwrap();
+        assert!(client
+            .add(&Source::Magnet(MAGNET.to_string()), &options())
+            .is_err());
+
+        server
+            .mock("POST", "/api/v2/torrents/add")
+            .match_body(Matcher::Regex("filename=\"x.torrent\"".to_string()))
+            .with_status(415)
+            .create();
+        let client = QBittorrent::new(Url::parse(&server.url()).unwrap(), None, None).unwrap();
+        let file = Source::File {
+            name: "x.torrent".to_string(),
+            bytes: b"not a torrent".to_vec(),
+        };
+        assert!(client.add(&file, &AddOptions::default()).is_err());
+    }
+
+    #[test]
+    fn transmission() {
+        let mut server = mockito::Server::new();
+        let conflict = server
+            .mock("POST", "/transmission/rpc")
+            .match_header("x-transmission-session-id", Matcher::Missing)
+            .with_status(409)
+            .with_header("X-Transmission-Session-Id", "abc123")
+            .create();
+        let add = server
+            .mock("POST", "/transmission/rpc")
+            .match_header("x-transmission-session-id", "abc123")
+            .match_header("authorization", Matcher::Regex("^Basic ".to_string()))
+            .match_body(Matcher::Json(serde_json::json!({
+                "method": "torrent-add",
+                "arguments": {
+                    "paused": true,
+                    "metainfo": "ZDFlZQ==",
+                    "labels": ["movies"],
+                    "download-dir": "/data/movies",
+                }
+            })))
+            .with_body(r#"{"result": "success", "arguments": {}}"#)
+            .create();
+
+        let client = Transmission::new(
+            Url::parse(&format!("{}/transmission/rpc", server.url())).unwrap(),
+            Some("admin".to_string()),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+        let file = Source::File {
+            name: "x.torrent".to_string(),
+            bytes: b"d1ee".to_vec(),
+        };
+        client.add(&file, &options()).unwrap();
+
+        conflict.assert();
+        add.assert();
+    }
+
+    #[test]
+    fn transmission_errors() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/transmission/rpc")
+            .with_body(r#"{"result": "invalid or corrupt torrent file"}"#)
+            .create();
+        let client = Transmission::new(
+            Url::parse(&format!("{}/transmission/rpc", server.url())).unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+        let error = client
+            .add(&Source::Magnet(MAGNET.to_string()), &AddOptions::default())
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid or corrupt"));
+    }
+}
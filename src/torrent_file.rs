@@ -0,0 +1,258 @@
+use crate::bencode::{self, Value};
+use crate::parse::api::Torrent;
+use crate::size::ByteSize;
+use sha1::{Digest, Sha1};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// The metadata of a .torrent file.
+#[derive(Debug, Clone)]
+pub struct TorrentFile {
+    /// Upper case hex, the way yts spells it.
+    pub info_hash: String,
+    pub name: String,
+    pub piece_length: u64,
+    pub files: Vec<FileEntry>,
+    /// Every tracker, `announce` first and then the `announce-list` tiers, without duplicates.
+    pub announce: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    /// Relative to the torrent's directory, `/` separated. Single file torrents have the torrent name.
+    pub path: String,
+    pub length: u64,
+}
+
+impl TorrentFile {
+    pub fn load(path: &Path) -> Result<TorrentFile, Box<dyn Error>> {
+        let bytes = std::fs::read(path)?;
+        TorrentFile::parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<TorrentFile, Box<dyn Error>> {
+        let raw_info = *bencode::raw_entries(bytes)?
+            .get(&b"info"[..])
+            .ok_or("there's no info dictionary")?;
+        let root = bencode::decode(bytes)?;
+        let info = root.get("info").ok_or("there's no info dictionary")?;
+
+        let name = info
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("there's no name")?
+            .to_string();
+        let piece_length = info
+            .get("piece length")
+            .and_then(Value::as_int)
+            .and_then(|i| u64::try_from(i).ok())
+            .ok_or("there's no valid piece length")?;
+
+        let files = match (info.get("length"), info.get("files")) {
+            (Some(length), _) => vec![FileEntry {
+                path: name.clone(),
+                length: size(length)?,
+            }],
+            (None, Some(Value::List(files))) => files
+                .iter()
+                .map(|file| {
+                    let path = file
+                        .get("path")
+                        .and_then(Value::as_list)
+                        .and_then(|parts| {
+                            parts
+                                .iter()
+                                .map(|part| part.as_str())
+                                .collect::<Option<Vec<&str>>>()
+                        })
+                        .ok_or("a file has no valid path")?
+                        .join("/");
+                    let length = size(file.get("length").ok_or("a file has no length")?)?;
+                    Ok(FileEntry { path, length })
+                })
+                .collect::<Result<Vec<FileEntry>, Box<dyn Error>>>()?,
+            _ => return Err("there are no files".into()),
+        };
+
+        let mut announce: Vec<String> = vec![];
+        let tiers = root
+            .get("announce-list")
+            .and_then(Value::as_list)
+            .unwrap_or_default();
+        for tracker in root.get("announce").into_iter().chain(
+            tiers
+                .iter()
+                .flat_map(|tier| tier.as_list().unwrap_or_default()),
+        ) {
+            if let Some(tracker) = tracker.as_str() {
+                if !announce.iter().any(|known| known == tracker) {
+                    announce.push(tracker.to_string());
+                }
+            }
+        }
+
+        Ok(TorrentFile {
+            info_hash: hex(&Sha1::digest(raw_info)),
+            name,
+            piece_length,
+            files,
+            announce,
+        })
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.length).sum()
+    }
+
+    /// Checks the file against what the api says about the torrent.
+    /// Every mismatch is part of the error.
+    pub fn verify(&self, torrent: &Torrent) -> Result<(), Box<dyn Error>> {
+        let mut mismatches = vec![];
+
+        match &torrent.hash {
+            Some(hash) if !hash.eq_ignore_ascii_case(&self.info_hash) => mismatches.push(format!(
+                "the info-hash is {} instead of {}",
+                self.info_hash, hash
+            )),
+            _ => {}
+        }
+        // yts works out size_bytes from the rounded size it shows, so both are only that precise
+        let tolerance = torrent
+            .size
+            .as_deref()
+            .and_then(|size| ByteSize::yts_precision(size).ok())
+            .map_or(0, ByteSize::bytes);
+        if let Some(size) = torrent.bytes() {
+            if self.total_size().abs_diff(size.bytes()) > tolerance {
+                mismatches.push(format!(
+                    "the size is {} bytes instead of about {}",
+                    self.total_size(),
+                    size.bytes()
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches.join(", ").into())
+        }
+    }
+}
+
+fn size(value: &Value) -> Result<u64, Box<dyn Error>> {
+    value
+        .as_int()
+        .and_then(|i| u64::try_from(i).ok())
+        .ok_or_else(|| "invalid file length".into())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+impl fmt::Display for TorrentFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name:         {}", self.name)?;
+        writeln!(f, "info-hash:    {}", self.info_hash)?;
+        writeln!(f, "piece length: {}", self.piece_length)?;
+        writeln!(f, "total size:   {}", self.total_size())?;
+        writeln!(f, "files:")?;
+        for file in &self.files {
+            writeln!(f, "  {} ({})", file.path, file.length)?;
+        }
+        writeln!(f, "trackers:")?;
+        for tracker in &self.announce {
+            writeln!(f, "  {}", tracker)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::{ListResponse, Torrent};
+    use crate::torrent_file::TorrentFile;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    const MULTI: &[u8] = b"d8:announce9:udp://one13:announce-listll9:udp://oneel9:udp://twoee4:infod5:filesld6:lengthi3e4:pathl3:sub5:a.mkveed6:lengthi4e4:pathl5:b.srteee4:name4:test12:piece lengthi16384e6:pieces0:ee";
+
+    fn torrent() -> Torrent {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()[0]
+            .torrents
+            .as_ref()
+            .unwrap()[0]
+            .clone()
+    }
+
+    #[test]
+    fn parses() {
+        let file = TorrentFile::parse(MULTI).unwrap();
+        assert_eq!(file.name, "test");
+        assert_eq!(file.piece_length, 16384);
+        assert_eq!(file.total_size(), 7);
+        assert_eq!(file.files[0].path, "sub/a.mkv");
+        assert_eq!(file.announce, vec!["udp://one", "udp://two"]);
+        // sha1 of the raw info dictionary
+        assert_eq!(file.info_hash, "05B927EE96CCEDAAC1572AEE1B2A0F906073C0AD");
+    }
+
+    #[test]
+    fn single_file() {
+        let file =
+            TorrentFile::parse(b"d4:infod6:lengthi42e4:name5:a.mkv12:piece lengthi1e6:pieces0:ee")
+                .unwrap();
+        assert_eq!(file.files.len(), 1);
+        assert_eq!(file.files[0].path, "a.mkv");
+        assert_eq!(file.total_size(), 42);
+        assert!(file.announce.is_empty());
+    }
+
+    #[test]
+    fn verifies() {
+        let file = TorrentFile::parse(MULTI).unwrap();
+        let mut torrent = torrent();
+        assert!(file.verify(&torrent).is_err());
+
+        torrent.hash = Some(file.info_hash.to_lowercase());
+        torrent.size = None;
+        torrent.size_bytes = Some(7);
+        assert!(file.verify(&torrent).is_ok());
+
+        torrent.size_bytes = Some(8);
+        let error = file.verify(&torrent).unwrap_err().to_string();
+        assert_eq!(error, "the size is 7 bytes instead of about 8");
+    }
+
+    #[test]
+    fn verifies_rounded_sizes() {
+        // the fixture's torrent says "958.3 MB" and 1004850381 bytes, which is 958.3 MiB exactly
+        let file = |length: u64| {
+            let raw = format!(
+                "d4:infod6:lengthi{}e4:name5:a.mkv12:piece lengthi1e6:pieces0:ee",
+                length
+            );
+            TorrentFile::parse(raw.as_bytes()).unwrap()
+        };
+        let mut torrent = torrent();
+        assert_eq!(torrent.size.as_deref(), Some("958.3 MB"));
+        assert_eq!(torrent.size_bytes, Some(1004850381));
+
+        let actual = file(1004810000); // 958.26 MiB
+        torrent.hash = Some(actual.info_hash.clone());
+        assert!(actual.verify(&torrent).is_ok());
+
+        let other = file(1004950000); // 958.40 MiB
+        torrent.hash = Some(other.info_hash.clone());
+        assert!(other.verify(&torrent).is_err());
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_torrent() {
+        assert!(TorrentFile::parse(b"<html></html>").is_err());
+        assert!(TorrentFile::parse(b"d4:name1:xe").is_err());
+    }
+}
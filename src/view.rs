@@ -0,0 +1,224 @@
+use crate::imdb::{ImdbData, ImdbInfo};
+use crate::lists::MovieList;
+use crate::parse::api::{ListResponse, Movie, MovieDescription};
+use colored::Colorize;
+use hyphenation::{Language, Load, Standard};
+use prettytable::{format, Cell, Row, Table};
+use serde::Serialize;
+use std::fmt;
+use textwrap::{fill, Options as TextWrapOptions, WordSplitter};
+
+/// What's shown next to the movies besides what the api says, when it was asked for.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Extras<'a> {
+    pub imdb: Option<&'a ImdbData>,
+    pub seen: Option<&'a MovieList>,
+}
+
+/// A movie with the local knowledge about it, as it gets printed. That knowledge doesn't come
+/// from the api, so it's kept out of [`Movie`].
+#[derive(Serialize, Debug)]
+pub struct MovieView<'a> {
+    #[serde(flatten)]
+    pub movie: &'a Movie,
+    /// What the IMDb datasets say about it, when they were asked for. Null when they don't
+    /// know the movie.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imdb_info: Option<Option<&'a ImdbInfo>>,
+    /// Whether it's on the seen list, when that was asked for.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub seen: bool,
+}
+
+impl<'a> MovieView<'a> {
+    pub fn new(movie: &'a Movie, extras: Extras<'a>) -> MovieView<'a> {
+        MovieView {
+            movie,
+            imdb_info: extras.imdb.map(|imdb| imdb.get(movie)),
+            seen: extras.seen.is_some_and(|seen| seen.contains(movie)),
+        }
+    }
+
+    /// The title, with `(seen) ` in front of seen movies.
+    pub fn title(&self) -> String {
+        if self.seen {
+            format!("(seen) {}", self.movie.title())
+        } else {
+            self.movie.title()
+        }
+    }
+
+    /// What the IMDb datasets say about the movie, e.g. `312 votes, directed by Luigi Magni`.
+    /// Empty unless they were asked for.
+    pub fn imdb_details(&self) -> String {
+        let info = match self.imdb_info {
+            Some(Some(info)) => info,
+            Some(None) => return "no IMDb data".into(),
+            None => return "".into(),
+        };
+        let mut details = vec![];
+        if let Some(votes) = info.votes {
+            details.push(format!("{} votes", votes));
+        }
+        if !info.directors.is_empty() {
+            details.push(format!("directed by {}", info.directors.join(", ")));
+        }
+        if !info.writers.is_empty() {
+            details.push(format!("written by {}", info.writers.join(", ")));
+        }
+        if let Some(original) = &info.original_title {
+            if Some(original) != self.movie.title.as_ref() {
+                details.push(format!("originally {}", original));
+            }
+        }
+        details.join(", ")
+    }
+}
+
+/// A list response as it gets printed.
+pub struct ListView<'a> {
+    pub list: &'a ListResponse,
+    pub extras: Extras<'a>,
+}
+
+impl<'a> ListView<'a> {
+    pub fn new(list: &'a ListResponse, extras: Extras<'a>) -> ListView<'a> {
+        ListView { list, extras }
+    }
+
+    pub fn movies(&self) -> Vec<MovieView<'a>> {
+        self.list
+            .movies()
+            .iter()
+            .map(|movie| MovieView::new(movie, self.extras))
+            .collect()
+    }
+}
+
+impl fmt::Display for ListView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_COLSEP); // NO_BORDER?
+
+        let data = match &self.list.data {
+            Some(data) => data,
+            None => {
+                writeln!(f, "missing data from response")?;
+                return Ok(());
+            }
+        };
+        if data.movies.as_ref().is_none_or(|movies| movies.is_empty()) {
+            writeln!(f, "no movies in response")?;
+            return Ok(());
+        }
+
+        for view in self.movies() {
+            let movie = view.movie;
+            let left = format!(
+                "{rating}\n\n{year}\n{genres}\n\n{id}",
+                rating = movie.rating().as_str().green(),
+                year = movie.year().as_str().green(),
+                genres = fill(movie.genres().as_str(), 12),
+                id = movie.id(),
+            );
+            let right = format!(
+                "{title}\n{url}\n{yt}\n{imdb}\n{torrents}\n\n{summary}",
+                title = view.title().as_str().bright_green(),
+                url = movie.url(),
+                yt = movie.youtube(),
+                imdb = [movie.imdb(), view.imdb_details()]
+                    .iter()
+                    .filter(|line| !line.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                torrents = movie.torrent_sizes(),
+                summary = {
+                    let text = movie.text(MovieDescription::Summary);
+                    let dictionary = Standard::from_embedded(Language::EnglishUS).unwrap();
+                    let options = TextWrapOptions::new(90)
+                        .word_splitter(WordSplitter::Hyphenation(dictionary));
+                    fill(text.as_str(), &options)
+                },
+            );
+            let cells = vec![Cell::new(right.as_str()), Cell::new(left.as_str())];
+            table.add_row(Row::new(cells));
+        }
+
+        f.write_fmt(format_args!("{}", table))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::imdb::{ImdbData, ImdbInfo};
+    use crate::lists::MovieList;
+    use crate::parse::api::ListResponse;
+    use crate::view::{Extras, ListView};
+    use chrono::Utc;
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    #[test]
+    fn flags_seen_movies() {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let mut seen = MovieList::default();
+        seen.add(response.movies()[0].clone());
+        let view = ListView::new(
+            &response,
+            Extras {
+                seen: Some(&seen),
+                ..Extras::default()
+            },
+        );
+
+        let json = serde_json::to_value(view.movies()).unwrap();
+        assert_eq!(json[0]["seen"], true);
+        assert_eq!(
+            json[0]["title"], "La via dei babbuini",
+            "the title stays as it is"
+        );
+        assert!(json[1].get("seen").is_none());
+        assert!(view.to_string().contains("(seen) La via dei babbuini"));
+        assert!(!response.to_string().contains("(seen)"));
+    }
+
+    #[test]
+    fn shows_imdb_details() {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        let imdb = ImdbData {
+            built: Utc::now(),
+            titles: [(
+                "tt0144665".to_string(),
+                ImdbInfo {
+                    original_title: Some("La via dei babbuini".into()),
+                    votes: Some(312),
+                    directors: vec!["Luigi Magni".into()],
+                    ..ImdbInfo::default()
+                },
+            )]
+            .into(),
+        };
+        let view = ListView::new(
+            &response,
+            Extras {
+                imdb: Some(&imdb),
+                ..Extras::default()
+            },
+        );
+        let movies = view.movies();
+        assert_eq!(
+            movies[0].imdb_details(),
+            "312 votes, directed by Luigi Magni"
+        );
+        assert_eq!(movies[1].imdb_details(), "no IMDb data");
+        let json = serde_json::to_value(&movies).unwrap();
+        assert_eq!(json[0]["imdb_info"]["votes"], 312);
+        assert!(json[1]["imdb_info"].is_null());
+        assert_eq!(
+            ListView::new(&response, Extras::default()).movies()[1].imdb_details(),
+            ""
+        );
+    }
+}
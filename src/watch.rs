@@ -0,0 +1,213 @@
+use crate::parse::api::Movie;
+use crate::request::client_builder;
+use crate::snapshot::{diff, Change};
+use crate::store::{load_json_or_default, save_json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+use url::Url;
+
+/// What a watcher has already seen, so that only new things get reported.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WatchState {
+    movies: BTreeSet<u32>,
+    hashes: BTreeSet<String>,
+}
+
+/// Where watch events get delivered.
+#[derive(Debug)]
+pub enum Sink {
+    /// One line per event on stdout.
+    Stdout,
+    /// A shell command ran once per event, with the event details in `YTS_*` environment variables.
+    Command(String),
+    /// The event as json, posted to the url.
+    Webhook(Url),
+}
+
+impl WatchState {
+    /// Loads the state saved at `path`. A missing file means nothing was seen yet.
+    pub fn load(path: &Path) -> Result<WatchState, Box<dyn Error>> {
+        load_json_or_default(path, "a watch state")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        save_json(path, self)
+    }
+
+    /// The movies as far as they were already seen: the known ones, with only the known torrents.
+    fn seen(&self, movies: &[Movie]) -> Vec<Movie> {
+        movies
+            .iter()
+            .filter(|movie| movie.id.is_some_and(|id| self.movies.contains(&id)))
+            .cloned()
+            .map(|mut movie| {
+                if let Some(torrents) = movie.torrents.as_mut() {
+                    torrents.retain(|t| {
+                        t.hash
+                            .as_ref()
+                            .is_some_and(|hash| self.hashes.contains(&hash.to_uppercase()))
+                    });
+                }
+                movie
+            })
+            .collect()
+    }
+
+    /// Records the movies as seen and returns whatever wasn't seen before, as the
+    /// [`diff`] from what was seen of them: new movies and new torrents for known ones.
+    /// The torrents of a new movie are part of its change and don't get changes of their own.
+    pub fn update(&mut self, movies: &[Movie]) -> Vec<Change> {
+        let changes = diff(&self.seen(movies), movies, false);
+
+        for movie in movies {
+            let id = match movie.id {
+                Some(id) => id,
+                None => continue,
+            };
+            self.movies.insert(id);
+            self.hashes.extend(
+                movie
+                    .torrents
+                    .iter()
+                    .flatten()
+                    .filter_map(|t| Some(t.hash.as_deref()?.to_uppercase())),
+            );
+        }
+
+        changes
+    }
+}
+
+/// The event name of a change, e.g. `new_torrent`.
+fn event(change: &Change) -> String {
+    change.kind().replace(' ', "_")
+}
+
+/// The qualities a change is about.
+fn qualities(change: &Change) -> String {
+    match change {
+        Change::NewTorrent { torrent, .. } | Change::RemovedTorrent { torrent, .. } => {
+            torrent.quality.clone().unwrap_or_default()
+        }
+        _ => change
+            .movie()
+            .torrents
+            .iter()
+            .flatten()
+            .filter_map(|t| t.quality.clone())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+/// The one line a change gets reported with, e.g. `new torrent: Title (2019) [2160p] url`.
+pub fn message(change: &Change) -> String {
+    let movie = change.movie();
+    format!(
+        "{what}: {title} ({year}) [{qualities}] {url}",
+        what = change.kind(),
+        title = movie.title(),
+        year = movie.year(),
+        qualities = qualities(change),
+        url = movie.url(),
+    )
+}
+
+impl Sink {
+    pub fn emit(&self, change: &Change) -> Result<(), Box<dyn Error>> {
+        match self {
+            Sink::Stdout => println!("{}", message(change)),
+            Sink::Command(command) => {
+                let movie = change.movie();
+                let status = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("YTS_EVENT", event(change))
+                    .env("YTS_MESSAGE", message(change))
+                    .env("YTS_ID", movie.id())
+                    .env("YTS_TITLE", movie.title())
+                    .env("YTS_YEAR", movie.year())
+                    .env("YTS_QUALITY", qualities(change))
+                    .env("YTS_URL", movie.url())
+                    .status()?;
+                if !status.success() {
+                    return Err(format!("notification command failed with {}", status).into());
+                }
+            }
+            Sink::Webhook(url) => {
+                client_builder()
+                    .build()?
+                    .post(url.clone())
+                    .json(change)
+                    .send()?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::api::{ListResponse, Movie};
+    use crate::snapshot::Change;
+    use crate::watch::{message, WatchState};
+    static JSON: &str = include_str!("parse/test-data/list.json");
+
+    fn movies() -> Vec<Movie> {
+        let response: ListResponse = serde_json::from_str(JSON).unwrap();
+        response.data.unwrap().movies.unwrap()
+    }
+
+    #[test]
+    fn reports_only_new_things() {
+        let mut state = WatchState::default();
+        let mut movies = movies();
+
+        let changes = state.update(&movies);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| matches!(c, Change::NewMovie { .. })));
+
+        assert!(state.update(&movies).is_empty());
+
+        let mut torrent = movies[0].torrents.as_ref().unwrap()[0].clone();
+        torrent.quality = Some("2160p".to_string());
+        torrent.hash = Some("2160P0000000000000000000000000000000000".to_string());
+        movies[0].torrents.as_mut().unwrap().push(torrent);
+
+        let changes = state.update(&movies);
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            Change::NewTorrent { movie, torrent } => {
+                assert_eq!(movie.title(), "La via dei babbuini");
+                assert_eq!(torrent.quality.as_deref(), Some("2160p"));
+            }
+            c => panic!("unexpected change {:?}", c),
+        }
+        assert_eq!(
+            message(&changes[0]),
+            "new torrent: La via dei babbuini (1974) [2160p] https://yts.mx/movies/la-via-dei-babbuini-1974"
+        );
+
+        // a torrent that drops off and comes back isn't new, and neither are rating changes
+        let torrent = movies[0].torrents.as_mut().unwrap().pop().unwrap();
+        movies[1].rating = Some(7.5);
+        assert!(state.update(&movies).is_empty());
+        movies[0].torrents.as_mut().unwrap().push(torrent);
+        assert!(state.update(&movies).is_empty());
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let mut state = WatchState::default();
+        state.update(&movies());
+        let path = std::env::temp_dir().join(format!("yts-watch-{}.json", std::process::id()));
+        state.save(&path).unwrap();
+        let mut loaded = WatchState::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.update(&movies()).is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::thread::sleep;
+use std::time::Duration;
+use yts::replay::REPLAY_VAR;
+use yts::request::{DetailsRequest, ListRequest, MovieRef};
+
+/// Responses in the format YTS_RECORD writes, made from src/parse/test-data/list.json rather
+/// than recorded, so that they don't change with what yts lists.
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay")
+}
+
+/// A home of its own, so the user's config and data don't leak into the tests.
+fn home(name: &str) -> PathBuf {
+    let home = std::env::temp_dir().join(format!("yts-it-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    home
+}
+
+fn yts(args: &[&str]) -> Output {
+    let home = home("cli");
+    Command::new(env!("CARGO_BIN_EXE_yts"))
+        .args(args)
+        .env(REPLAY_VAR, fixtures())
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn library_replays() {
+    std::env::set_var(REPLAY_VAR, fixtures());
+
+    let list = ListRequest::new().limit(2).execute().unwrap();
+    assert_eq!(list.data.unwrap().movies.unwrap().len(), 2);
+
+    let movie = DetailsRequest::new(MovieRef::Id(32080)).execute().unwrap();
+    assert_eq!(movie.unwrap().title(), "La via dei babbuini");
+
+    assert!(DetailsRequest::new(MovieRef::Id(1)).execute().is_err());
+}
+
+#[test]
+fn cli_replays() {
+    let output = yts(&["list", "-l", "2", "--format", "json"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\"title\": \"La via dei babbuini\""));
+    assert!(stdout.contains("\"title\": \"The Dating List\""));
+
+    let output = yts(&["list", "-l", "3"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn server_replays() {
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let home = home("http");
+    let mut server = Command::new(env!("CARGO_BIN_EXE_http"))
+        .env(REPLAY_VAR, fixtures())
+        .env("YTS_HTTP_BIND", address.to_string())
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .spawn()
+        .unwrap();
+
+    let get = |path: &str| reqwest::blocking::get(format!("http://{}{}", address, path));
+    let mut ready = false;
+    for _ in 0..50 {
+        if get("/healthcheck").is_ok() {
+            ready = true;
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+
+    let nfo = ready.then(|| get("/movies/32080/nfo").and_then(|r| r.text()));
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let nfo = nfo.expect("the server didn't come up").unwrap();
+    assert!(nfo.contains("<title>La via dei babbuini</title>"));
+}